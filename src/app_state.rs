@@ -1,108 +1,580 @@
-use std::sync::Arc;
-use std::sync::Mutex;
+use super::camera::{CameraMode, OrbitCamera, ViewTransform};
+use std::cell::RefCell;
+use std::rc::Rc;
 
-lazy_static! {
-    static ref APP_STATE: Mutex<Arc<AppState>> = Mutex::new(Arc::new(AppState::new()));
-}
-
-pub fn update_dynamic_data(time: f32, canvas_height: f32, canvas_width: f32) {
+const BUTTON_SECONDARY: i16 = 2;
+const MAX_POINTERS: usize = 2;
+const WHEEL_ZOOM_RATE: f32 = 0.001;
+const PINCH_ZOOM_RATE: f32 = 0.01;
+const KEY_LOOK_SPEED: f32 = std::f32::consts::FRAC_PI_2 / 1000.0;
+const KEY_ZOOM_RATE: f32 = 1.0 / 1000.0;
 
-    let min_height_width = canvas_height.min(canvas_width);
-    let display_size = 0.9*min_height_width;
-    let half_display_size = display_size / 2.0;
-    let half_canvas_height = canvas_height / 2.0;
-    let half_canvas_width = canvas_width / 2.0;
+const KEY_UP: u8 = 1;
+const KEY_DOWN: u8 = 1 << 1;
+const KEY_LEFT: u8 = 1 << 2;
+const KEY_RIGHT: u8 = 1 << 3;
+const KEY_ZOOM_IN: u8 = 1 << 4;
+const KEY_ZOOM_OUT: u8 = 1 << 5;
 
-    let mut data = APP_STATE.lock().unwrap();
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scene {
+    Cube,
+    Graph,
+}
 
-    *data = Arc::new(AppState {
-        canvas_height: canvas_height,
-        canvas_width: canvas_width,
+/// Everything that can change `AppState`. Applying the same events in the
+/// same order to the same starting state always gives the same result.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    Resize { canvas_height: f32, canvas_width: f32 },
+    Tick { time: f32 },
+    PointerDown { id: i32, x: f32, y: f32, button: i16 },
+    PointerMove { id: i32, x: f32, y: f32 },
+    PointerUp { id: i32 },
+    Wheel { delta_y: f32, is_pinch: bool },
+    Key { key: String, is_down: bool },
+    /// Focus left the canvas, so any key releases will not be seen.
+    ReleaseKeys,
+    Look { rotation_x_delta: f32, rotation_y_delta: f32 },
+    Pan { dx: f32, dy: f32 },
+    Zoom { factor: f32 },
+    ResetCamera,
+    SetCameraMode(CameraMode),
+    SetCameraTarget([f32; 3]),
+    SetCameraDamping(f32),
+    SetScene(Scene),
+    SetAxes(bool),
+    SetContours(bool),
+    SetWireframe(bool),
+}
 
-        control_bottom: half_canvas_height - half_display_size,
-        control_top: half_canvas_height + half_display_size,
-        control_left: half_canvas_width - half_display_size,
-        control_right: half_canvas_width + half_display_size,
+/// Events raised by DOM handlers between frames, drained by `GameClient`.
+pub type EventQueue = Rc<RefCell<Vec<Event>>>;
 
-        time: time,
-        ..*data.clone()
-    });
+fn get_key_bit(key: &str) -> u8 {
+    match key {
+        "ArrowUp" | "w" => KEY_UP,
+        "ArrowDown" | "s" => KEY_DOWN,
+        "ArrowLeft" | "a" => KEY_LEFT,
+        "ArrowRight" | "d" => KEY_RIGHT,
+        "+" | "=" => KEY_ZOOM_IN,
+        "-" | "_" => KEY_ZOOM_OUT,
+        _ => 0,
+    }
 }
 
-pub fn get_curr_state() -> Arc<AppState> {
-    APP_STATE.lock().unwrap().clone()
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pointer {
+    pub id: i32,
+    pub button: i16,
+    pub x: f32,
+    pub y: f32,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct AppState {
+    pub camera: OrbitCamera,
+    pub camera_mode: CameraMode,
     pub canvas_height: f32,
     pub canvas_width: f32,
     pub control_bottom: f32,
     pub control_top: f32,
     pub control_left: f32,
     pub control_right: f32,
-    pub mouse_down: bool,
-    pub mouse_x: f32,
-    pub mouse_y: f32,
+    pub cursor: Option<(f32, f32)>,
+    pub held_keys: u8,
+    pub pointers: [Option<Pointer>; MAX_POINTERS],
     pub rotation_x_axis: f32,
     pub rotation_y_axis: f32,
+    pub scene: Scene,
+    pub show_axes: bool,
+    pub show_contours: bool,
+    pub show_wireframe: bool,
     pub time: f32,
 }
 
 impl AppState {
-    fn new() -> Self {
+    pub fn new() -> Self {
         Self {
+            camera: OrbitCamera::new(),
+            camera_mode: CameraMode::Turntable,
             canvas_height: 0.0,
             canvas_width: 0.0,
             control_bottom: 0.0,
             control_top: 0.0,
             control_left: 0.0,
             control_right: 0.0,
-            mouse_down: false,
-            mouse_x: -1.0,
-            mouse_y: -1.0,
+            cursor: None,
+            held_keys: 0,
+            pointers: [None; MAX_POINTERS],
             rotation_x_axis: -0.5,
             rotation_y_axis: -0.5,
+            scene: Scene::Cube,
+            show_axes: false,
+            show_contours: false,
+            show_wireframe: false,
             time: 0.0,
         }
     }
 
+    pub fn get_view(&self) -> ViewTransform {
+        match self.camera_mode {
+            CameraMode::Turntable => ViewTransform {
+                rotation_x_axis: self.rotation_x_axis,
+                rotation_y_axis: self.rotation_y_axis,
+                ..self.camera.get_view()
+            },
+            CameraMode::Orbit => self.camera.get_view(),
+        }
+    }
 
-}
+    fn get_pixels_per_model_unit(&self) -> f32 {
+        0.2 * (self.control_top - self.control_bottom)
+    }
 
-pub fn update_mouse_down(x: f32, y: f32, is_down: bool) {
-    let mut data = APP_STATE.lock().unwrap();
-    *data = Arc::new(AppState {
-        mouse_down: is_down,
-        mouse_x: x,
-        mouse_y: data.canvas_height - y,
-        ..*data.clone()
-    })
-}
+    pub fn apply(&self, event: &Event) -> Self {
+        match *event {
+            Event::Resize { canvas_height, canvas_width } => self.with_size(canvas_height, canvas_width),
+            Event::Tick { time } => self.with_tick(time),
+            Event::PointerDown { id, x, y, button } => self.with_pointer_down(id, x, y, button).with_cursor(x, y),
+            Event::PointerMove { id, x, y } => self.with_pointer_move(id, x, y).with_cursor(x, y),
+            Event::PointerUp { id } => self.with_pointer_up(id),
+            Event::Wheel { delta_y, is_pinch } => self.with_wheel(delta_y, is_pinch),
+            Event::Key { ref key, is_down } => self.with_key(key, is_down),
+            Event::ReleaseKeys => Self { held_keys: 0, ..*self },
+            Event::Look { rotation_x_delta, rotation_y_delta } => self.with_look(rotation_x_delta, rotation_y_delta),
+            Event::Pan { dx, dy } => self.with_pan(dx, dy, 1.0),
+            Event::Zoom { factor } => self.with_camera(|camera| camera.zoom_by(factor)),
+            Event::ResetCamera => self.with_camera_reset(),
+            Event::SetCameraMode(camera_mode) => Self { camera_mode: camera_mode, ..*self },
+            Event::SetCameraTarget(target) => self.with_camera(|camera| camera.set_target(target)),
+            Event::SetCameraDamping(damping) => self.with_camera(|camera| camera.damping = damping),
+            Event::SetScene(scene) => Self { scene: scene, ..*self },
+            Event::SetAxes(show_axes) => Self { show_axes: show_axes, ..*self },
+            Event::SetContours(show_contours) => Self { show_contours: show_contours, ..*self },
+            Event::SetWireframe(show_wireframe) => Self { show_wireframe: show_wireframe, ..*self },
+        }
+    }
+
+    fn with_camera(&self, update: impl FnOnce(&mut OrbitCamera)) -> Self {
+        let mut camera = self.camera;
+        update(&mut camera);
+
+        Self {
+            camera: camera,
+            ..*self
+        }
+    }
+
+    /// Resets the orbit camera and the turntable angles alike, so either mode
+    /// returns to its starting view.
+    fn with_camera_reset(&self) -> Self {
+        Self {
+            rotation_x_axis: -0.5,
+            rotation_y_axis: -0.5,
+            ..self.with_camera(|camera| camera.reset())
+        }
+    }
+
+    /// Pans along the screen axes of the rotation on show, which in turntable
+    /// mode is not the orbit camera's own.
+    fn with_pan(&self, dx: f32, dy: f32, units_per_model: f32) -> Self {
+        let view = self.get_view();
+        self.with_camera(|camera| {
+            let mut oriented = OrbitCamera {
+                pitch: view.rotation_x_axis,
+                yaw: view.rotation_y_axis,
+                ..*camera
+            };
+            oriented.pan(dx, dy, units_per_model);
+            camera.target = oriented.target;
+        })
+    }
+
+    fn with_size(&self, canvas_height: f32, canvas_width: f32) -> Self {
+        let min_height_width = canvas_height.min(canvas_width);
+        let display_size = 0.9*min_height_width;
+        let half_display_size = display_size / 2.0;
+        let half_canvas_height = canvas_height / 2.0;
+        let half_canvas_width = canvas_width / 2.0;
+
+        Self {
+            canvas_height: canvas_height,
+            canvas_width: canvas_width,
+
+            control_bottom: half_canvas_height - half_display_size,
+            control_top: half_canvas_height + half_display_size,
+            control_left: half_canvas_width - half_display_size,
+            control_right: half_canvas_width + half_display_size,
+
+            ..*self
+        }
+    }
+
+    /// Advances to `time`, applying held keys over the elapsed interval and
+    /// letting the orbit camera coast.
+    fn with_tick(&self, time: f32) -> Self {
+        let elapsed = (time - self.time).max(0.0);
+        let is_held = |bit: u8| if self.held_keys & bit != 0 { 1.0 } else { 0.0 };
+
+        let pitch = (is_held(KEY_UP) - is_held(KEY_DOWN)) * KEY_LOOK_SPEED * elapsed;
+        let yaw = (is_held(KEY_RIGHT) - is_held(KEY_LEFT)) * KEY_LOOK_SPEED * elapsed;
+        let zoom = (is_held(KEY_ZOOM_IN) - is_held(KEY_ZOOM_OUT)) * KEY_ZOOM_RATE * elapsed;
+
+        let mut state = if pitch != 0.0 || yaw != 0.0 { self.with_look(pitch, yaw) } else { *self };
+        if zoom != 0.0 {
+            state.camera.zoom_by(zoom.exp());
+        }
+
+        state.camera.step(elapsed);
+        Self {
+            time: time,
+            ..state
+        }
+    }
+
+    fn with_key(&self, key: &str, is_down: bool) -> Self {
+        if is_down && (key == "r" || key == "Home") {
+            return self.with_camera_reset();
+        }
+
+        let bit = get_key_bit(key);
+        let held_keys = if is_down { self.held_keys | bit } else { self.held_keys & !bit };
+
+        Self {
+            held_keys: held_keys,
+            ..*self
+        }
+    }
+
+    /// The latest pointer position, pressed or not, with y measured up from
+    /// the bottom of the canvas.
+    fn with_cursor(&self, x: f32, y: f32) -> Self {
+        Self {
+            cursor: Some((x, self.canvas_height - y)),
+            ..*self
+        }
+    }
+
+    fn with_pointer_down(&self, id: i32, x: f32, y: f32, button: i16) -> Self {
+        let mut pointers = self.pointers;
+        let pointer = Pointer { id: id, button: button, x: x, y: self.canvas_height - y };
+
+        let existing = pointers.iter().position(|slot| matches!(slot, Some(p) if p.id == id));
+        match existing.or_else(|| pointers.iter().position(|slot| slot.is_none())) {
+            Some(index) => pointers[index] = Some(pointer),
+            None => return *self,
+        }
+
+        Self {
+            pointers: pointers,
+            ..*self
+        }
+    }
+
+    fn with_pointer_up(&self, id: i32) -> Self {
+        let mut pointers = self.pointers;
+        for slot in pointers.iter_mut() {
+            if matches!(slot, Some(p) if p.id == id) {
+                *slot = None;
+            }
+        }
+
+        Self {
+            pointers: pointers,
+            ..*self
+        }
+    }
+
+    /// One pointer rotates (or pans, when it is a secondary mouse button in
+    /// orbit mode); two pointers pinch-zoom by their change in separation and
+    /// pan by the movement of their midpoint.
+    fn with_pointer_move(&self, id: i32, x: f32, y: f32) -> Self {
+        let index = match self.pointers.iter().position(|slot| matches!(slot, Some(p) if p.id == id)) {
+            Some(index) => index,
+            None => return *self,
+        };
+
+        let previous = self.pointers[index].unwrap();
+        let inverted_y = self.canvas_height - y;
+        let x_delta = x - previous.x;
+        let y_delta = inverted_y - previous.y;
 
-pub fn update_mouse_position(x: f32, y: f32) {
-    let mut data = APP_STATE.lock().unwrap();
-    let inverted_y = data.canvas_height - y;
-    let x_delta = x - data.mouse_x;
-    let y_delta = inverted_y - data.mouse_y;
-
-    let rotation_x_delta = if data.mouse_down {
-        std::f32::consts::PI * y_delta / data.canvas_height
-    } else {
-        0.0
-    };
-
-    let rotation_y_delta = if data.mouse_down {
-        std::f32::consts::PI * x_delta / data.canvas_width
-    } else {
-        0.0
-    };
-
-    *data = Arc::new(AppState {
-        mouse_x: x,
-        mouse_y: inverted_y,
-        rotation_x_axis: data.rotation_x_axis + rotation_x_delta,
-        rotation_y_axis: data.rotation_y_axis + rotation_y_delta,
-        ..*data.clone()
-    })
+        let mut pointers = self.pointers;
+        pointers[index] = Some(Pointer { x: x, y: inverted_y, ..previous });
+
+        let other = self.pointers.iter().enumerate().find_map(|(i, slot)| if i != index { *slot } else { None });
+        match other {
+            Some(other) => {
+                let old_distance = (previous.x - other.x).hypot(previous.y - other.y);
+                let new_distance = (x - other.x).hypot(inverted_y - other.y);
+                let mut camera = self.camera;
+                if old_distance > 0.0 {
+                    camera.zoom_by(new_distance / old_distance);
+                }
+
+                let zoomed = Self {
+                    camera: camera,
+                    pointers: pointers,
+                    ..*self
+                };
+                zoomed.with_pan(x_delta / 2.0, y_delta / 2.0, self.get_pixels_per_model_unit())
+            },
+            None if self.camera_mode == CameraMode::Orbit && previous.button == BUTTON_SECONDARY => Self {
+                pointers: pointers,
+                ..self.with_pan(x_delta, y_delta, self.get_pixels_per_model_unit())
+            },
+            None => {
+                let rotation_x_delta = std::f32::consts::PI * y_delta / self.canvas_height;
+                let rotation_y_delta = std::f32::consts::PI * x_delta / self.canvas_width;
+                Self {
+                    pointers: pointers,
+                    ..self.with_look(rotation_x_delta, rotation_y_delta)
+                }
+            },
+        }
+    }
+
+    fn with_look(&self, rotation_x_delta: f32, rotation_y_delta: f32) -> Self {
+        let mut camera = self.camera;
+
+        match self.camera_mode {
+            CameraMode::Turntable => Self {
+                rotation_x_axis: self.rotation_x_axis + rotation_x_delta,
+                rotation_y_axis: self.rotation_y_axis + rotation_y_delta,
+                ..*self
+            },
+            CameraMode::Orbit => {
+                camera.rotate(rotation_x_delta, rotation_y_delta);
+                Self {
+                    camera: camera,
+                    ..*self
+                }
+            },
+        }
+    }
+
+    /// Trackpad pinches arrive as wheel events with `ctrl_key` set and small
+    /// deltas, so they zoom at a higher rate.
+    fn with_wheel(&self, delta_y: f32, is_pinch: bool) -> Self {
+        let rate = if is_pinch { PINCH_ZOOM_RATE } else { WHEEL_ZOOM_RATE };
+
+        let mut camera = self.camera;
+        camera.zoom_by((-delta_y * rate).exp());
+
+        Self {
+            camera: camera,
+            ..*self
+        }
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUTTON_PRIMARY: i16 = 0;
+
+    fn get_state(camera_mode: CameraMode) -> AppState {
+        apply_all(AppState::new(), &[
+            Event::SetCameraMode(camera_mode),
+            Event::SetCameraDamping(0.0),
+            Event::Resize { canvas_height: 500.0, canvas_width: 1000.0 },
+        ])
+    }
+
+    fn apply_all(state: AppState, events: &[Event]) -> AppState {
+        events.iter().fold(state, |state, event| state.apply(event))
+    }
+
+    fn down(id: i32, x: f32, y: f32, button: i16) -> Event {
+        Event::PointerDown { id: id, x: x, y: y, button: button }
+    }
+
+    fn move_to(id: i32, x: f32, y: f32) -> Event {
+        Event::PointerMove { id: id, x: x, y: y }
+    }
+
+    #[test]
+    fn resize_centres_square_control_box() {
+        let state = get_state(CameraMode::Turntable);
+
+        assert_eq!((state.control_bottom, state.control_top), (25.0, 475.0));
+        assert_eq!((state.control_left, state.control_right), (275.0, 725.0));
+    }
+
+    #[test]
+    fn single_pointer_drag_rotates_turntable() {
+        let state = apply_all(get_state(CameraMode::Turntable), &[
+            down(1, 100.0, 300.0, BUTTON_PRIMARY),
+            move_to(1, 200.0, 250.0),
+        ]);
+
+        let pi = std::f32::consts::PI;
+        assert!((state.rotation_y_axis - (-0.5 + pi * 100.0 / 1000.0)).abs() < 1e-6);
+        assert!((state.rotation_x_axis - (-0.5 + pi * 50.0 / 500.0)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn moves_without_a_pressed_pointer_are_ignored() {
+        let state = apply_all(get_state(CameraMode::Turntable), &[
+            move_to(1, 200.0, 250.0),
+            down(1, 100.0, 300.0, BUTTON_PRIMARY),
+            Event::PointerUp { id: 1 },
+            move_to(1, 300.0, 100.0),
+        ]);
+
+        assert_eq!((state.rotation_x_axis, state.rotation_y_axis), (-0.5, -0.5));
+        assert_eq!(state.pointers, [None, None]);
+        assert_eq!(state.cursor, Some((300.0, 400.0)));
+    }
+
+    #[test]
+    fn single_pointer_drag_orbits_after_tick() {
+        let state = apply_all(get_state(CameraMode::Orbit), &[
+            down(7, 500.0, 250.0, BUTTON_PRIMARY),
+            move_to(7, 600.0, 250.0),
+            Event::Tick { time: 16.0 },
+        ]);
+
+        assert!((state.get_view().rotation_y_axis - (-0.5 + std::f32::consts::PI / 10.0)).abs() < 1e-6);
+        assert_eq!(state.rotation_y_axis, -0.5);
+        assert_eq!(state.time, 16.0);
+    }
+
+    #[test]
+    fn secondary_button_drag_pans_orbit_target() {
+        let state = apply_all(get_state(CameraMode::Orbit), &[
+            down(1, 500.0, 250.0, BUTTON_SECONDARY),
+            move_to(1, 590.0, 250.0),
+        ]);
+
+        assert!(state.camera.target[0] < 0.0);
+        assert_eq!(state.camera.pitch, -0.5);
+        assert_eq!(state.camera.yaw, -0.5);
+    }
+
+    #[test]
+    fn two_finger_pinch_zooms_by_separation_ratio() {
+        let state = apply_all(get_state(CameraMode::Orbit), &[
+            down(1, 400.0, 250.0, BUTTON_PRIMARY),
+            down(2, 500.0, 250.0, BUTTON_PRIMARY),
+            move_to(2, 600.0, 250.0),
+        ]);
+
+        assert!((state.camera.zoom - 2.0).abs() < 1e-6);
+        assert_eq!((state.camera.pitch, state.camera.yaw), (-0.5, -0.5));
+    }
+
+    #[test]
+    fn two_finger_drag_pans_without_zooming() {
+        let state = apply_all(get_state(CameraMode::Orbit), &[
+            down(1, 400.0, 250.0, BUTTON_PRIMARY),
+            down(2, 500.0, 250.0, BUTTON_PRIMARY),
+            move_to(1, 400.0, 200.0),
+            move_to(2, 500.0, 200.0),
+        ]);
+
+        assert!((state.camera.zoom - 1.0).abs() < 1e-5);
+        assert!(state.camera.target[1] < 0.0);
+    }
+
+    #[test]
+    fn turntable_view_zooms_and_pans() {
+        let state = apply_all(get_state(CameraMode::Turntable), &[
+            Event::Wheel { delta_y: -100.0, is_pinch: false },
+            down(1, 400.0, 250.0, BUTTON_PRIMARY),
+            down(2, 500.0, 250.0, BUTTON_PRIMARY),
+            move_to(1, 400.0, 200.0),
+            move_to(2, 500.0, 200.0),
+        ]);
+
+        let view = state.get_view();
+        assert!((view.zoom - 0.1f32.exp()).abs() < 1e-5);
+        assert!(view.target[1] < 0.0);
+        assert_eq!((view.rotation_x_axis, view.rotation_y_axis), (-0.5, -0.5));
+    }
+
+    #[test]
+    fn turntable_pans_along_its_own_screen_axes() {
+        let state = apply_all(get_state(CameraMode::Turntable), &[
+            Event::Look { rotation_x_delta: 0.5, rotation_y_delta: 0.5 },
+            Event::Pan { dx: 1.0, dy: 0.0 },
+        ]);
+
+        assert!((state.get_view().target[0] + 1.0).abs() < 1e-6);
+        assert!(state.get_view().target[1].abs() < 1e-6);
+    }
+
+    #[test]
+    fn reset_camera_restores_turntable_angles() {
+        let state = apply_all(get_state(CameraMode::Turntable), &[
+            down(1, 100.0, 300.0, BUTTON_PRIMARY),
+            move_to(1, 200.0, 250.0),
+            Event::PointerUp { id: 1 },
+            Event::ResetCamera,
+        ]);
+
+        let view = state.get_view();
+        assert_eq!((view.rotation_x_axis, view.rotation_y_axis), (-0.5, -0.5));
+        assert_eq!(view.zoom, 1.0);
+    }
+
+    #[test]
+    fn extra_pointers_are_ignored() {
+        let state = apply_all(get_state(CameraMode::Orbit), &[
+            down(1, 400.0, 250.0, BUTTON_PRIMARY),
+            down(2, 500.0, 250.0, BUTTON_PRIMARY),
+            down(3, 600.0, 250.0, BUTTON_PRIMARY),
+            move_to(3, 700.0, 250.0),
+        ]);
+
+        assert_eq!(state.pointers.iter().filter(|p| p.is_some()).count(), 2);
+        assert_eq!(state.camera.zoom, 1.0);
+        assert_eq!(state.camera.target, [0.0; 3]);
+    }
+
+    #[test]
+    fn held_keys_rotate_on_tick_until_released() {
+        let key = |is_down: bool| Event::Key { key: String::from("ArrowRight"), is_down: is_down };
+        let state = apply_all(get_state(CameraMode::Turntable), &[
+            key(true),
+            Event::Tick { time: 1000.0 },
+            key(false),
+            Event::Tick { time: 2000.0 },
+        ]);
+
+        assert!((state.rotation_y_axis - (-0.5 + std::f32::consts::FRAC_PI_2)).abs() < 1e-5);
+        assert_eq!(state.held_keys, 0);
+    }
+
+    #[test]
+    fn losing_focus_releases_held_keys() {
+        let key = |key: &str| Event::Key { key: String::from(key), is_down: true };
+        let state = apply_all(get_state(CameraMode::Turntable), &[
+            key("ArrowRight"),
+            key("+"),
+            Event::ReleaseKeys,
+            Event::Tick { time: 1000.0 },
+        ]);
+
+        assert_eq!(state.held_keys, 0);
+        assert_eq!(state.rotation_y_axis, -0.5);
+    }
+
+    #[test]
+    fn same_events_give_identical_state() {
+        let events = vec![
+            Event::Resize { canvas_height: 720.0, canvas_width: 1280.0 },
+            Event::SetCameraMode(CameraMode::Orbit),
+            down(1, 640.0, 360.0, BUTTON_PRIMARY),
+            move_to(1, 700.0, 300.0),
+            Event::Tick { time: 16.7 },
+            Event::Wheel { delta_y: -120.0, is_pinch: false },
+            Event::PointerUp { id: 1 },
+            Event::Tick { time: 33.4 },
+        ];
+
+        assert_eq!(apply_all(AppState::new(), &events), apply_all(AppState::new(), &events));
+    }
+}
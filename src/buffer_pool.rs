@@ -0,0 +1,314 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use web_sys::WebGlBuffer;
+use web_sys::WebGlRenderingContext as GL;
+
+#[derive(Clone, Copy, Default)]
+pub struct BufferStats {
+    pub buffer_count: usize,
+    pub bytes_allocated: usize,
+    pub bytes_uploaded: usize,
+    pub last_frame_bytes_uploaded: usize,
+    pub draw_calls: usize,
+    pub triangles: usize,
+}
+
+pub struct BufferPool {
+    gl: GL,
+    stats: Rc<Cell<BufferStats>>,
+    enabled_attributes: Rc<Cell<u32>>,
+}
+
+impl BufferPool {
+    pub fn new(gl: &GL) -> Self {
+        Self {
+            gl: gl.clone(),
+            stats: Rc::new(Cell::new(BufferStats::default())),
+            enabled_attributes: Rc::new(Cell::new(0)),
+        }
+    }
+
+    pub fn stats(&self) -> BufferStats {
+        self.stats.get()
+    }
+
+    pub fn begin_frame(&self) {
+        let mut stats = self.stats.get();
+        stats.last_frame_bytes_uploaded = stats.bytes_uploaded;
+        stats.bytes_uploaded = 0;
+        stats.draw_calls = 0;
+        stats.triangles = 0;
+        self.stats.set(stats);
+    }
+
+    pub fn create_array_buffer(&self, data: &[f32], usage: u32) -> BufferHandle {
+        let handle = self.create_buffer(GL::ARRAY_BUFFER, usage);
+        handle.update_f32(data);
+        handle
+    }
+
+    pub fn create_dynamic_array_buffer(&self, len: usize) -> BufferHandle {
+        let handle = self.create_buffer(GL::ARRAY_BUFFER, GL::DYNAMIC_DRAW);
+        handle.bind();
+        self.gl.buffer_data_with_i32(GL::ARRAY_BUFFER, 4 * len as i32, GL::DYNAMIC_DRAW);
+        handle.0.set_byte_length(4 * len);
+        handle
+    }
+
+    pub fn create_index_buffer(&self, data: &[u16], usage: u32) -> BufferHandle {
+        let handle = self.create_buffer(GL::ELEMENT_ARRAY_BUFFER, usage);
+        handle.update_u16(data);
+        handle
+    }
+
+    fn create_buffer(&self, target: u32, usage: u32) -> BufferHandle {
+        let buffer = self.gl.create_buffer().ok_or("Failed to create buffer").unwrap();
+
+        let mut stats = self.stats.get();
+        stats.buffer_count += 1;
+        self.stats.set(stats);
+
+        BufferHandle(Rc::new(BufferEntry {
+            gl: self.gl.clone(),
+            buffer: RefCell::new(Some(buffer)),
+            target: target,
+            usage: usage,
+            byte_length: Cell::new(0),
+            stats: self.stats.clone(),
+            enabled_attributes: self.enabled_attributes.clone(),
+        }))
+    }
+}
+
+struct BufferEntry {
+    gl: GL,
+    buffer: RefCell<Option<WebGlBuffer>>,
+    target: u32,
+    usage: u32,
+    byte_length: Cell<usize>,
+    stats: Rc<Cell<BufferStats>>,
+    enabled_attributes: Rc<Cell<u32>>,
+}
+
+impl BufferEntry {
+    fn set_byte_length(&self, byte_length: usize) {
+        let mut stats = self.stats.get();
+        stats.bytes_allocated = stats.bytes_allocated - self.byte_length.get() + byte_length;
+        self.stats.set(stats);
+        self.byte_length.set(byte_length);
+    }
+
+    fn add_bytes_uploaded(&self, bytes: usize) {
+        let mut stats = self.stats.get();
+        stats.bytes_uploaded += bytes;
+        self.stats.set(stats);
+    }
+
+    fn add_draw_call(&self, triangles: usize) {
+        let mut stats = self.stats.get();
+        stats.draw_calls += 1;
+        stats.triangles += triangles;
+        self.stats.set(stats);
+    }
+
+    /// Leaves exactly the attribute locations in the `locations` bit set
+    /// enabled, so arrays from an earlier mesh never feed this one's draw.
+    fn set_enabled_attributes(&self, locations: u32) {
+        let enabled = self.enabled_attributes.get();
+        for location in 0..32 {
+            let bit = 1 << location;
+            if locations & bit != 0 && enabled & bit == 0 {
+                self.gl.enable_vertex_attrib_array(location);
+            } else if locations & bit == 0 && enabled & bit != 0 {
+                self.gl.disable_vertex_attrib_array(location);
+            }
+        }
+        self.enabled_attributes.set(locations);
+    }
+
+    fn release(&self) {
+        if let Some(buffer) = self.buffer.borrow_mut().take() {
+            self.gl.delete_buffer(Some(&buffer));
+            self.set_byte_length(0);
+
+            let mut stats = self.stats.get();
+            stats.buffer_count -= 1;
+            self.stats.set(stats);
+        }
+    }
+}
+
+impl Drop for BufferEntry {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+/// A GL buffer shared between owners. The buffer is deleted when the last
+/// handle is dropped, or immediately for every owner by `delete`.
+#[derive(Clone)]
+pub struct BufferHandle(Rc<BufferEntry>);
+
+impl BufferHandle {
+    pub fn bind(&self) {
+        let entry = &self.0;
+        entry.gl.bind_buffer(entry.target, entry.buffer.borrow().as_ref());
+    }
+
+    pub fn byte_length(&self) -> usize {
+        self.0.byte_length.get()
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.0.buffer.borrow().is_none()
+    }
+
+    pub fn delete(&self) {
+        self.0.release();
+    }
+
+    pub fn update_f32(&self, data: &[f32]) {
+        if self.is_deleted() {
+            return;
+        }
+
+        self.bind();
+        // The view aliases wasm memory, so nothing may allocate before the upload
+        // has consumed it.
+        unsafe {
+            let array = js_sys::Float32Array::view(data);
+            self.0.gl.buffer_data_with_array_buffer_view(self.0.target, &array, self.0.usage);
+        }
+        self.0.set_byte_length(4 * data.len());
+        self.0.add_bytes_uploaded(4 * data.len());
+    }
+
+    /// Overwrites the start of the buffer in place, only reallocating when the
+    /// data no longer fits.
+    pub fn write_f32(&self, data: &[f32]) {
+        if self.is_deleted() {
+            return;
+        }
+
+        if 4 * data.len() > self.byte_length() {
+            self.update_f32(data);
+            return;
+        }
+
+        self.bind();
+        unsafe {
+            let array = js_sys::Float32Array::view(data);
+            self.0.gl.buffer_sub_data_with_i32_and_array_buffer_view(self.0.target, 0, &array);
+        }
+        self.0.add_bytes_uploaded(4 * data.len());
+    }
+
+    pub fn update_u16(&self, data: &[u16]) {
+        if self.is_deleted() {
+            return;
+        }
+
+        self.bind();
+        unsafe {
+            let array = js_sys::Uint16Array::view(data);
+            self.0.gl.buffer_data_with_array_buffer_view(self.0.target, &array, self.0.usage);
+        }
+        self.0.set_byte_length(2 * data.len());
+        self.0.add_bytes_uploaded(2 * data.len());
+    }
+}
+
+#[derive(Clone)]
+struct VertexAttribute {
+    location: u32,
+    size: i32,
+    buffer: BufferHandle,
+}
+
+#[derive(Clone)]
+pub struct MeshHandle {
+    attributes: Vec<VertexAttribute>,
+    indices: Option<BufferHandle>,
+    count: i32,
+}
+
+impl MeshHandle {
+    pub fn new(count: i32) -> Self {
+        Self {
+            attributes: Vec::new(),
+            indices: None,
+            count: count,
+        }
+    }
+
+    pub fn with_attribute(mut self, location: u32, size: i32, buffer: BufferHandle) -> Self {
+        self.attributes.push(VertexAttribute {
+            location: location,
+            size: size,
+            buffer: buffer,
+        });
+        self
+    }
+
+    pub fn with_indices(mut self, indices: BufferHandle) -> Self {
+        self.indices = Some(indices);
+        self
+    }
+
+    pub fn attribute(&self, location: u32) -> Option<&BufferHandle> {
+        self.attributes
+            .iter()
+            .find(|attribute| attribute.location == location)
+            .map(|attribute| &attribute.buffer)
+    }
+
+    pub fn indices(&self) -> Option<&BufferHandle> {
+        self.indices.as_ref()
+    }
+
+    pub fn bind(&self, gl: &GL) {
+        let mut locations = 0;
+        for attribute in self.attributes.iter() {
+            attribute.buffer.bind();
+            gl.vertex_attrib_pointer_with_i32(attribute.location, attribute.size, GL::FLOAT, false, 0, 0);
+            locations |= 1 << attribute.location;
+        }
+        if let Some(attribute) = self.attributes.first() {
+            attribute.buffer.0.set_enabled_attributes(locations);
+        }
+
+        if let Some(indices) = &self.indices {
+            indices.bind();
+        }
+    }
+
+    pub fn draw(&self, gl: &GL, mode: u32) {
+        self.draw_count(gl, mode, self.count);
+    }
+
+    pub fn draw_count(&self, gl: &GL, mode: u32, count: i32) {
+        match &self.indices {
+            Some(_) => gl.draw_elements_with_i32(mode, count, GL::UNSIGNED_SHORT, 0),
+            None => gl.draw_arrays(mode, 0, count),
+        }
+
+        let triangles = match mode {
+            GL::TRIANGLES => count / 3,
+            GL::TRIANGLE_STRIP | GL::TRIANGLE_FAN => (count - 2).max(0),
+            _ => 0,
+        };
+        if let Some(attribute) = self.attributes.first() {
+            attribute.buffer.0.add_draw_call(triangles as usize);
+        }
+    }
+
+    pub fn delete(&self) {
+        for attribute in self.attributes.iter() {
+            attribute.buffer.delete();
+        }
+
+        if let Some(indices) = &self.indices {
+            indices.delete();
+        }
+    }
+}
@@ -0,0 +1,137 @@
+const MIN_ZOOM: f32 = 0.1;
+const MAX_ZOOM: f32 = 10.0;
+const MAX_PITCH: f32 = std::f32::consts::FRAC_PI_2 - 0.01;
+const REFERENCE_FRAME_MS: f32 = 1000.0 / 60.0;
+const SETTLED_DELTA: f32 = 1e-5;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CameraMode {
+    Turntable,
+    Orbit,
+}
+
+/// What the renderers need to place the model: the same two rotation angles
+/// the turntable drag has always produced, plus a zoom factor and the model
+/// point that ends up at the centre of the control box.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ViewTransform {
+    pub rotation_x_axis: f32,
+    pub rotation_y_axis: f32,
+    pub zoom: f32,
+    pub target: [f32; 3],
+}
+
+impl ViewTransform {
+    pub fn lerp(&self, other: &ViewTransform, alpha: f32) -> Self {
+        let mix = |from: f32, to: f32| from + (to - from) * alpha;
+        Self {
+            rotation_x_axis: mix(self.rotation_x_axis, other.rotation_x_axis),
+            rotation_y_axis: mix(self.rotation_y_axis, other.rotation_y_axis),
+            zoom: mix(self.zoom, other.zoom),
+            target: [
+                mix(self.target[0], other.target[0]),
+                mix(self.target[1], other.target[1]),
+                mix(self.target[2], other.target[2]),
+            ],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OrbitCamera {
+    pub pitch: f32,
+    pub yaw: f32,
+    pub zoom: f32,
+    pub target: [f32; 3],
+    pub damping: f32,
+    pub default_target: [f32; 3],
+    pub pitch_delta: f32,
+    pub yaw_delta: f32,
+}
+
+impl OrbitCamera {
+    pub fn new() -> Self {
+        Self {
+            pitch: -0.5,
+            yaw: -0.5,
+            zoom: 1.0,
+            target: [0.0; 3],
+            damping: 0.15,
+            default_target: [0.0; 3],
+            pitch_delta: 0.0,
+            yaw_delta: 0.0,
+        }
+    }
+
+    pub fn get_view(&self) -> ViewTransform {
+        ViewTransform {
+            rotation_x_axis: self.pitch,
+            rotation_y_axis: self.yaw,
+            zoom: self.zoom,
+            target: self.target,
+        }
+    }
+
+    /// Queues a rotation that `step` applies, so releasing a drag leaves the
+    /// remaining delta to coast to a stop.
+    pub fn rotate(&mut self, pitch_delta: f32, yaw_delta: f32) {
+        self.pitch_delta += pitch_delta;
+        self.yaw_delta += yaw_delta;
+    }
+
+    pub fn zoom_by(&mut self, factor: f32) {
+        if factor > 0.0 && factor.is_finite() {
+            self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        }
+    }
+
+    /// Moves the target so the model follows a drag of `(dx, dy)` screen units,
+    /// where `units_per_model` is how many screen units one model unit spans
+    /// at zoom 1.
+    pub fn pan(&mut self, dx: f32, dy: f32, units_per_model: f32) {
+        let (sin_x, cos_x) = self.pitch.sin_cos();
+        let (sin_y, cos_y) = self.yaw.sin_cos();
+        let right = [cos_y, sin_y * sin_x, -sin_y * cos_x];
+        let up = [0.0, cos_x, sin_x];
+        let scale = 1.0 / (units_per_model * self.zoom);
+
+        for axis in 0..3 {
+            self.target[axis] -= (right[axis] * dx + up[axis] * dy) * scale;
+        }
+    }
+
+    pub fn set_target(&mut self, target: [f32; 3]) {
+        self.target = target;
+        self.default_target = target;
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self {
+            damping: self.damping,
+            default_target: self.default_target,
+            target: self.default_target,
+            ..Self::new()
+        };
+    }
+
+    /// Applies queued rotation. With damping the delta decays by `damping`
+    /// per 60Hz frame instead of being consumed at once.
+    pub fn step(&mut self, elapsed_ms: f32) {
+        let frames = (elapsed_ms / REFERENCE_FRAME_MS).max(0.0);
+        let applied = if self.damping > 0.0 {
+            1.0 - (1.0 - self.damping).powf(frames)
+        } else {
+            1.0
+        };
+
+        self.pitch = (self.pitch + self.pitch_delta * applied).clamp(-MAX_PITCH, MAX_PITCH);
+        self.yaw += self.yaw_delta * applied;
+        self.pitch_delta *= 1.0 - applied;
+        self.yaw_delta *= 1.0 - applied;
+
+        if self.pitch_delta.abs() < SETTLED_DELTA && self.yaw_delta.abs() < SETTLED_DELTA {
+            self.pitch_delta = 0.0;
+            self.yaw_delta = 0.0;
+        }
+    }
+}
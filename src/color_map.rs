@@ -0,0 +1,183 @@
+const VIRIDIS: [u32; 11] = [
+    0x440154, 0x482475, 0x414487, 0x355f8d, 0x2a788e, 0x21918c,
+    0x22a884, 0x44bf70, 0x7ad151, 0xbddf26, 0xfde725,
+];
+
+const MAGMA: [u32; 11] = [
+    0x000004, 0x140e36, 0x3b0f70, 0x641a80, 0x8c2981, 0xb73779,
+    0xde4968, 0xf7705c, 0xfe9f6d, 0xfecf92, 0xfcfdbf,
+];
+
+const TERRAIN: [(f32, u32); 6] = [
+    (0.0, 0x333399),
+    (0.15, 0x0099ff),
+    (0.25, 0x00cc66),
+    (0.5, 0xffff99),
+    (0.75, 0x805c54),
+    (1.0, 0xffffff),
+];
+
+fn hex_to_rgb(hex: u32) -> [f32; 3] {
+    [
+        ((hex >> 16) & 0xff) as f32 / 255.0,
+        ((hex >> 8) & 0xff) as f32 / 255.0,
+        (hex & 0xff) as f32 / 255.0,
+    ]
+}
+
+pub struct ColorMap {
+    stops: Vec<(f32, [f32; 3])>,
+}
+
+impl ColorMap {
+    pub fn flat(color: [f32; 3]) -> Self {
+        Self { stops: vec![(0.0, color)] }
+    }
+
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        let evenly_spaced = |colors: &[u32]| Self {
+            stops: colors
+                .iter()
+                .enumerate()
+                .map(|(i, &hex)| (i as f32 / (colors.len() - 1) as f32, hex_to_rgb(hex)))
+                .collect(),
+        };
+
+        match name {
+            "flat" => Ok(Self::flat([0.5, 0.5, 0.8])),
+            "viridis" => Ok(evenly_spaced(&VIRIDIS)),
+            "magma" => Ok(evenly_spaced(&MAGMA)),
+            "terrain" => Ok(Self {
+                stops: TERRAIN.iter().map(|&(position, hex)| (position, hex_to_rgb(hex))).collect(),
+            }),
+            _ => Err(format!("Unknown colour map: {}", name)),
+        }
+    }
+
+    /// Builds a gradient from flattened `[position, r, g, b, ...]` stops with
+    /// positions ascending in `0..=1` and channels in `0..=1`.
+    pub fn from_stops(stops: &[f32]) -> Result<Self, String> {
+        if stops.is_empty() || !stops.len().is_multiple_of(4) {
+            return Err(String::from("Colour stops must be a non-empty list of [position, r, g, b] groups"));
+        }
+
+        let mut parsed: Vec<(f32, [f32; 3])> = Vec::with_capacity(stops.len() / 4);
+        for stop in stops.chunks(4) {
+            if stop.iter().any(|value| !(0.0..=1.0).contains(value)) {
+                return Err(format!("Colour stop {:?} has values outside 0..1", stop));
+            }
+            if let Some(&(previous, _)) = parsed.last() {
+                if stop[0] < previous {
+                    return Err(String::from("Colour stop positions must be ascending"));
+                }
+            }
+            parsed.push((stop[0], [stop[1], stop[2], stop[3]]));
+        }
+
+        Ok(Self { stops: parsed })
+    }
+
+    pub fn sample(&self, t: f32) -> [f32; 3] {
+        let t = t.clamp(0.0, 1.0);
+        let first = self.stops[0];
+        if t <= first.0 {
+            return first.1;
+        }
+
+        for pair in self.stops.windows(2) {
+            let (start, start_color) = pair[0];
+            let (end, end_color) = pair[1];
+            if t <= end {
+                let frac = if end > start { (t - start) / (end - start) } else { 1.0 };
+                return [
+                    start_color[0] + frac * (end_color[0] - start_color[0]),
+                    start_color[1] + frac * (end_color[1] - start_color[1]),
+                    start_color[2] + frac * (end_color[2] - start_color[2]),
+                ];
+            }
+        }
+
+        self.stops[self.stops.len() - 1].1
+    }
+
+    /// Samples `steps` evenly spaced colours as flattened RGB, bottom of the
+    /// range first, for drawing legends.
+    pub fn get_ramp(&self, steps: usize) -> Vec<f32> {
+        let mut ramp = Vec::with_capacity(3 * steps);
+        for i in 0..steps {
+            let t = if steps > 1 { i as f32 / (steps - 1) as f32 } else { 0.0 };
+            ramp.extend_from_slice(&self.sample(t));
+        }
+        ramp
+    }
+
+    pub fn get_texture_data(&self, width: usize) -> Vec<u8> {
+        self.get_ramp(width)
+            .chunks(3)
+            .flat_map(|rgb| {
+                let to_byte = |channel: f32| (channel * 255.0).round() as u8;
+                vec![to_byte(rgb[0]), to_byte(rgb[1]), to_byte(rgb[2]), 255]
+            })
+            .collect()
+    }
+}
+
+pub fn get_y_range(y_vals: &[f32]) -> (f32, f32) {
+    let (min, max) = y_vals.iter().fold((f32::MAX, f32::MIN), |(min, max), &value| {
+        (min.min(value), max.max(value))
+    });
+
+    if min > max {
+        (0.0, 0.0)
+    } else {
+        (min, max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_rgb(actual: &[f32], expected: [f32; 3]) {
+        for (actual, expected) in actual.iter().zip(expected.iter()) {
+            assert!((actual - expected).abs() < 1e-5, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn named_maps_span_their_palettes() {
+        for &name in ["flat", "viridis", "magma", "terrain"].iter() {
+            assert!(ColorMap::from_name(name).is_ok(), "{}", name);
+        }
+        assert!(ColorMap::from_name("rainbow").is_err());
+
+        let viridis = ColorMap::from_name("viridis").unwrap();
+        assert_rgb(&viridis.sample(0.0), hex_to_rgb(VIRIDIS[0]));
+        assert_rgb(&viridis.sample(1.0), hex_to_rgb(VIRIDIS[10]));
+        assert_rgb(&viridis.sample(0.5), hex_to_rgb(VIRIDIS[5]));
+    }
+
+    #[test]
+    fn custom_stops_are_validated() {
+        assert!(ColorMap::from_stops(&[]).is_err());
+        assert!(ColorMap::from_stops(&[0.0, 1.0, 0.0]).is_err());
+        assert!(ColorMap::from_stops(&[0.0, 1.5, 0.0, 0.0]).is_err());
+        assert!(ColorMap::from_stops(&[0.0, f32::NAN, 0.0, 0.0]).is_err());
+        assert!(ColorMap::from_stops(&[0.5, 0.0, 0.0, 0.0, 0.25, 1.0, 1.0, 1.0]).is_err());
+        assert!(ColorMap::from_stops(&[0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0]).is_ok());
+    }
+
+    #[test]
+    fn ramp_interpolates_between_stops() {
+        let map = ColorMap::from_stops(&[0.0, 0.0, 0.0, 0.0, 0.5, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0]).unwrap();
+        let ramp = map.get_ramp(5);
+
+        assert_eq!(ramp.len(), 15);
+        assert_rgb(&ramp[0..3], [0.0, 0.0, 0.0]);
+        assert_rgb(&ramp[3..6], [0.5, 0.0, 0.0]);
+        assert_rgb(&ramp[6..9], [1.0, 0.0, 0.0]);
+        assert_rgb(&ramp[9..12], [1.0, 0.5, 0.5]);
+        assert_rgb(&ramp[12..15], [1.0, 1.0, 1.0]);
+        assert_rgb(&map.get_ramp(1), [0.0, 0.0, 0.0]);
+    }
+}
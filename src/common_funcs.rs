@@ -1,4 +1,6 @@
+use super::camera::ViewTransform;
 use super::constants::*;
+use super::grid::{GridLayout, Shading};
 use nalgebra::{Matrix4, Perspective3};
 use web_sys::*;
 use web_sys::WebGlRenderingContext as GL;
@@ -43,120 +45,126 @@ pub fn get_cube_normals() -> Vec<f32> {
     ]
 }
 
-pub fn get_grid_normals(n: usize, y_vals: &Vec<f32>) -> Vec<f32> {
-    let points_per_row = n + 1;
-    let graph_layout_width: f32 = 2.0;
-    let square_size: f32 = graph_layout_width / n as f32;
+fn get_grid_point(layout: &GridLayout, y_vals: &[f32], index: usize) -> (f32, f32, f32) {
+    let points_per_row = layout.points_per_row();
+    let (x, z) = layout.position(index % points_per_row, index / points_per_row);
+    (x, y_vals[index], z)
+}
 
-    let mut return_var: Vec<f32> = vec![0.0; 3 * points_per_row * points_per_row];
+fn get_triangle_cross(a: (f32, f32, f32), b: (f32, f32, f32), c: (f32, f32, f32)) -> (f32, f32, f32) {
+    let u = (b.0 - a.0, b.1 - a.1, b.2 - a.2);
+    let v = (c.0 - a.0, c.1 - a.1, c.2 - a.2);
 
-    for z in 0..points_per_row {
-        for x in 0..points_per_row {
-            let y_val_index_a = z * points_per_row + x;
-            let return_var_start_index = 3 * y_val_index_a;
-            if z == n || x == n {
-                return_var[return_var_start_index + 1] = 1.0;
+    (
+        u.1 * v.2 - u.2 * v.1,
+        u.2 * v.0 - u.0 * v.2,
+        u.0 * v.1 - u.1 * v.0,
+    )
+}
+
+/// Per-vertex normals for the grid of `y_vals` laid out like
+/// `get_position_grid`. Smooth shading sums the unnormalised cross products of
+/// every adjacent triangle, so larger triangles weigh more; flat shading gives
+/// each vertex the normal of the first triangle of the cell it anchors, with
+/// the last row and column borrowing from their neighbouring cell.
+pub fn get_grid_normals(layout: &GridLayout, y_vals: &[f32], shading: Shading) -> Vec<f32> {
+    let points_per_row = layout.points_per_row();
+    let point = |index: usize| get_grid_point(layout, y_vals, index);
+
+    let mut return_var: Vec<f32> = vec![0.0; 3 * layout.point_count()];
+
+    match shading {
+        Shading::Smooth => {
+            for z in 0..layout.rows {
+                for x in 0..layout.columns {
+                    let top_left = z * points_per_row + x;
+                    let top_right = top_left + 1;
+                    let bottom_left = top_left + points_per_row;
+                    let bottom_right = bottom_left + 1;
+
+                    for triangle in [[top_left, bottom_left, bottom_right], [top_left, bottom_right, top_right]].iter() {
+                        let cross = get_triangle_cross(point(triangle[0]), point(triangle[1]), point(triangle[2]));
+                        for &index in triangle.iter() {
+                            return_var[3 * index] += cross.0;
+                            return_var[3 * index + 1] += cross.1;
+                            return_var[3 * index + 2] += cross.2;
+                        }
+                    }
+                }
             }
-            else {
-                let y_val_index_b = y_val_index_a + points_per_row;
-                let y_val_index_c = y_val_index_a + 1;
-
-                let x_val_1 = square_size * x as f32;
-                let x_val_2 = x_val_1 + square_size;
-
-                let z_val_1 = square_size * z as f32;
-                let z_val_2 = z_val_1 + square_size;
-
-                let normals = get_normal_vec(
-                    x_val_1,
-                    y_vals[y_val_index_a],
-                    z_val_1,
-                    x_val_1,
-                    y_vals[y_val_index_b],
-                    z_val_2,
-                    x_val_2,
-                    y_vals[y_val_index_c],
-                    z_val_2
-                );
-
-                return_var[return_var_start_index + 0] = normals.0;
-                return_var[return_var_start_index + 1] = normals.1;
-                return_var[return_var_start_index + 2] = normals.2;
+        },
+        Shading::Flat => {
+            for z in 0..layout.rows + 1 {
+                for x in 0..points_per_row {
+                    let top_left = z.min(layout.rows - 1) * points_per_row + x.min(layout.columns - 1);
+                    let cross = get_triangle_cross(
+                        point(top_left),
+                        point(top_left + points_per_row),
+                        point(top_left + points_per_row + 1),
+                    );
+
+                    let start = 3 * (z * points_per_row + x);
+                    return_var[start..start + 3].copy_from_slice(&[cross.0, cross.1, cross.2]);
+                }
             }
+        },
+    }
+
+    for normal in return_var.chunks_mut(3) {
+        let size = (normal[0] * normal[0] + normal[1] * normal[1] + normal[2] * normal[2]).sqrt();
+        if size > 0.0 && size.is_finite() {
+            normal.iter_mut().for_each(|value| *value /= size);
+        } else {
+            normal.copy_from_slice(&[0.0, 1.0, 0.0]);
         }
     }
 
     return_var
 }
 
-pub fn get_normal_vec(
-    point_a_x: f32,
-    point_a_y: f32,
-    point_a_z: f32,
-    point_b_x: f32,
-    point_b_y: f32,
-    point_b_z: f32,
-    point_c_x: f32,
-    point_c_y: f32,
-    point_c_z: f32,
-) -> (f32, f32, f32) {
-    let u_x = point_b_x - point_a_x;
-    let u_y = point_b_y - point_a_y;
-    let u_z = point_b_z - point_a_z;
-
-    let v_x = point_c_x - point_a_x;
-    let v_y = point_c_y - point_a_y;
-    let v_z = point_c_z - point_a_z;
-
-    let normal_x = u_y * v_z - v_y * u_z;
-    let normal_y = -1.0 * (u_x * v_z - v_x * u_z);
-    let normal_z = u_x * v_y - v_x * u_y;
-
-    let normal_size = (normal_x * normal_x + normal_y * normal_y + normal_z * normal_z).sqrt();
+pub fn get_ripple_y(x: f32, z: f32, curr_time: f32) -> f32 {
+    let frequency_scale: f32 = FREQ * std::f32::consts::PI;
+    let time_shift = curr_time * RIPPLE_TIME_SCALE;
 
-    (
-        normal_x / normal_size,
-        normal_y / normal_size,
-        normal_z / normal_size,
-    )
+    RIPPLE_AMPLITUDE * (frequency_scale * (x * x + z * z).sqrt() + time_shift).sin()
 }
 
-pub fn get_updated_3d_y_values(curr_time: f32) -> Vec<f32> {
-    let point_count_per_row = GRID_SIZE + 1;
-    let mut y_vals: Vec<f32> = vec![0.; point_count_per_row * point_count_per_row];
-    let half_grid: f32  = point_count_per_row as f32 / 2.0;
-    let frequency_scale: f32 = FREQ * std::f32::consts::PI;
-    let y_scale = 0.15;
-
-    let time_shift = curr_time / 500.0;
+pub fn get_updated_3d_y_values(layout: &GridLayout, curr_time: f32) -> Vec<f32> {
+    let points_per_row = layout.points_per_row();
+    let mut y_vals: Vec<f32> = vec![0.; layout.point_count()];
 
-    for z in 0..point_count_per_row {
-        for x in 0..point_count_per_row {
-            let use_y_index = z * point_count_per_row + x;
-            let scaled_x = frequency_scale * (x as f32 - half_grid) / half_grid;
-            let scaled_z = frequency_scale * (z as f32 - half_grid) / half_grid;
-            y_vals[use_y_index] = y_scale * ((scaled_x * scaled_x + scaled_z * scaled_z).sqrt() + time_shift).sin();
+    for z in 0..layout.rows + 1 {
+        for x in 0..points_per_row {
+            let (pos_x, pos_z) = layout.position(x, z);
+            y_vals[z * points_per_row + x] = get_ripple_y(pos_x, pos_z, curr_time);
         }
     }
 
     y_vals
 }
 
+/// Where a 3D view is drawn: the control box within the canvas, in pixels,
+/// and the camera looking into it.
+#[derive(Clone, Copy)]
+pub struct ViewportParams<'a> {
+    pub bottom: f32,
+    pub top: f32,
+    pub left: f32,
+    pub right: f32,
+    pub canvas_height: f32,
+    pub canvas_width: f32,
+    pub view: &'a ViewTransform,
+}
+
 pub struct Matrices3D {
     pub normals_rotation: [f32; 16],
     pub projection: [f32; 16],
 }
 
-pub fn get_3d_matrices(
-    bottom: f32,
-    top: f32,
-    left: f32,
-    right: f32,
-    canvas_height: f32,
-    canvas_width: f32,
-    rotation_angle_x_axis: f32,
-    rotation_angle_y_axis: f32,
-) -> Matrices3D {
+pub fn get_3d_matrices(viewport: &ViewportParams) -> Matrices3D {
+    let ViewportParams { bottom, top, left, right, canvas_height, canvas_width, view } = *viewport;
+    let rotation_angle_x_axis = view.rotation_x_axis;
+    let rotation_angle_y_axis = view.rotation_y_axis;
 
     let mut return_var = Matrices3D {
         normals_rotation: [0.0; 16],
@@ -178,6 +186,7 @@ pub fn get_3d_matrices(
     ];
 
     let rotation_matrix = mult_matrix_4(rotation_x_axis, rotation_y_axis);
+    let target_matrix = translation_matrix(-view.target[0], -view.target[1], -view.target[2]);
 
     let aspect: f32 = canvas_width / canvas_height;
     let scale_x = (right - left) / canvas_width;
@@ -190,15 +199,25 @@ pub fn get_3d_matrices(
         Z_PLANE,
     );
 
-    let scale_matrix = scaling_matrix(scale * 0.4, scale * 0.4 , 0.0);
-    let rotation_scale = mult_matrix_4(rotation_matrix, scale_matrix);
-    let combined_transform = mult_matrix_4(rotation_scale, translation_matrix);
+    let target_rotation = mult_matrix_4(target_matrix, rotation_matrix);
 
     let perspective_matrix_tmp: Perspective3<f32> = Perspective3::new(aspect, FIELD_OF_VIEW, Z_NEAR, Z_FAR);
     let mut perspective: [f32; 16] = [0.0; 16];
     perspective.copy_from_slice(perspective_matrix_tmp.as_matrix().as_slice());
 
-    return_var.projection = mult_matrix_4(combined_transform, perspective);
+    let project = |depth_scale: f32| {
+        let scale_matrix = scaling_matrix(scale * 0.4 * view.zoom, scale * 0.4 * view.zoom, depth_scale);
+        let rotation_scale = mult_matrix_4(target_rotation, scale_matrix);
+        let combined_transform = mult_matrix_4(rotation_scale, translation_matrix);
+        mult_matrix_4(combined_transform, perspective)
+    };
+
+    // The view stays flattened onto Z_PLANE; only the clip z row sees depth.
+    return_var.projection = project(0.0);
+    let with_depth = project(DEPTH_SCALE);
+    for column in 0..4 {
+        return_var.projection[4 * column + 2] = with_depth[4 * column + 2];
+    }
 
     let normal_matrix = Matrix4::new(
         rotation_matrix[0],
@@ -230,25 +249,23 @@ pub fn get_3d_matrices(
 }
 
 
-pub fn get_position_grid_n_by_n(n: usize) -> (Vec<f32>, Vec<u16>) {
-    let n_plus_one = n + 1;
-    let mut positions: Vec<f32> = vec![0.; 3 * n_plus_one * n_plus_one];
-    let mut indices: Vec<u16> = vec![0; 6 * n * n];
-
-    let graph_layout_width: f32 = 2.;
-    let square_size: f32 = graph_layout_width / n as f32;
+pub fn get_position_grid(layout: &GridLayout) -> (Vec<f32>, Vec<u16>) {
+    let points_per_row = layout.points_per_row();
+    let mut positions: Vec<f32> = vec![0.; 3 * layout.point_count()];
+    let mut indices: Vec<u16> = vec![0; 6 * layout.columns * layout.rows];
 
-    for z in 0..n_plus_one {
-        for x in 0..n_plus_one {
-            let start_pos_i = 3 * (z * n_plus_one + x);
-            positions[start_pos_i + 0] = -1. + (x as f32) * square_size;
+    for z in 0..layout.rows + 1 {
+        for x in 0..points_per_row {
+            let start_pos_i = 3 * (z * points_per_row + x);
+            let (pos_x, pos_z) = layout.position(x, z);
+            positions[start_pos_i + 0] = pos_x;
             positions[start_pos_i + 1] = 0.;
-            positions[start_pos_i + 2] = -1. + (z as f32) * square_size;
+            positions[start_pos_i + 2] = pos_z;
 
-            if z < n && x < n {
-                let start_index_i = 6 * (z * n + x);
-                let vertex_index_top_left = (z * n_plus_one + x) as u16;
-                let vertex_index_bottom_left = vertex_index_top_left + n_plus_one as u16;
+            if z < layout.rows && x < layout.columns {
+                let start_index_i = 6 * (z * layout.columns + x);
+                let vertex_index_top_left = (z * points_per_row + x) as u16;
+                let vertex_index_bottom_left = vertex_index_top_left + points_per_row as u16;
                 let vertex_index_top_right = vertex_index_top_left + 1;
                 let vertex_index_bottom_right = vertex_index_bottom_left + 1;
 
@@ -265,6 +282,45 @@ pub fn get_position_grid_n_by_n(n: usize) -> (Vec<f32>, Vec<u16>) {
     (positions, indices)
 }
 
+/// Reorders the triangles in `indices` so the farthest from the camera come
+/// first, for drawing transparent geometry. Distance is the clip-space `w` of
+/// each triangle's centroid, which grows with depth in front of the camera.
+pub fn get_back_to_front_indices(projection: &[f32; 16], positions: &[f32], indices: &[u16]) -> Vec<u16> {
+    let mut triangles: Vec<(f32, &[u16])> = indices
+        .chunks(3)
+        .map(|triangle| {
+            let mut centroid = [0.0; 3];
+            for &index in triangle.iter() {
+                for (axis, value) in centroid.iter_mut().enumerate() {
+                    *value += positions[3 * index as usize + axis] / 3.0;
+                }
+            }
+            (transform_point(projection, centroid[0], centroid[1], centroid[2])[3], triangle)
+        })
+        .collect();
+    triangles.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+
+    triangles.iter().flat_map(|(_, triangle)| triangle.iter().cloned()).collect()
+}
+
+pub fn get_edge_indices(triangle_indices: &[u16]) -> Vec<u16> {
+    let mut seen = std::collections::HashSet::new();
+    let mut edges: Vec<u16> = Vec::new();
+
+    for triangle in triangle_indices.chunks(3) {
+        for i in 0..3 {
+            let a = triangle[i];
+            let b = triangle[(i + 1) % 3];
+            if seen.insert((a.min(b), a.max(b))) {
+                edges.push(a);
+                edges.push(b);
+            }
+        }
+    }
+
+    edges
+}
+
 
 pub fn get_position_cube() -> (Vec<f32>, Vec<u16>) {
     let mut positions: Vec<f32> = vec![
@@ -318,10 +374,20 @@ pub fn get_position_cube() -> (Vec<f32>, Vec<u16>) {
 
 
 
+/// Drawing buffer `(height, width)` for a canvas of the given CSS size, never
+/// smaller than one pixel.
+pub fn get_drawing_buffer_size(css_height: f32, css_width: f32, pixels_per_css_pixel: f32) -> (u32, u32) {
+    let to_pixels = |css: f32| (css * pixels_per_css_pixel).round().max(1.0) as u32;
+    (to_pixels(css_height), to_pixels(css_width))
+}
+
+/// Links the two shaders, binding each name in `attributes` to its index as
+/// the attribute location the program's meshes use.
 pub fn link_program(
     gl: &GL,
     vert_source: &str,
     frag_source: &str,
+    attributes: &[&str],
 ) -> Result<WebGlProgram, String> {
     let program = gl
         .create_program()
@@ -332,6 +398,9 @@ pub fn link_program(
 
         gl.attach_shader(&program, &vert_shader);
         gl.attach_shader(&program, &frag_shader);
+        for (location, name) in attributes.iter().enumerate() {
+            gl.bind_attrib_location(&program, location as u32, name);
+        }
         gl.link_program(&program);
 
         if gl.get_program_parameter(&program, GL::LINK_STATUS)
@@ -370,6 +439,39 @@ fn compile_shader(
 }
 
 
+pub fn create_texture(gl: &GL, filter: u32) -> WebGlTexture {
+    let texture = gl.create_texture().ok_or("Failed to create texture").unwrap();
+    gl.bind_texture(GL::TEXTURE_2D, Some(&texture));
+    gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, filter as i32);
+    gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, filter as i32);
+    gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_S, GL::CLAMP_TO_EDGE as i32);
+    gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_T, GL::CLAMP_TO_EDGE as i32);
+    texture
+}
+
+pub fn upload_texture_rgba(gl: &GL, texture: &WebGlTexture, width: usize, height: usize, pixels: &[u8]) {
+    gl.bind_texture(GL::TEXTURE_2D, Some(texture));
+    gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+        GL::TEXTURE_2D,
+        0,
+        GL::RGBA as i32,
+        width as i32,
+        height as i32,
+        0,
+        GL::RGBA,
+        GL::UNSIGNED_BYTE,
+        Some(pixels),
+    ).unwrap();
+}
+
+pub fn transform_point(matrix: &[f32; 16], x: f32, y: f32, z: f32) -> [f32; 4] {
+    let mut clip = [0.0; 4];
+    for (row, value) in clip.iter_mut().enumerate() {
+        *value = matrix[row] * x + matrix[4 + row] * y + matrix[8 + row] * z + matrix[12 + row];
+    }
+    clip
+}
+
 pub fn translation_matrix(tx: f32, ty: f32, tz: f32) -> [f32; 16] {
     let mut mat = [0.0; 16];
 
@@ -420,4 +522,148 @@ pub fn mult_matrix_4(a: [f32; 16], b: [f32; 16]) -> [f32; 16] {
     mat[15] = a[12] * b[3] + a[13] * b[7] + a[14] * b[11] + a[15] * b[15];
 
     mat
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_surface(layout: &GridLayout, f: impl Fn(f32, f32) -> f32) -> Vec<f32> {
+        let mut y_vals = Vec::with_capacity(layout.point_count());
+        for z in 0..layout.rows + 1 {
+            for x in 0..layout.points_per_row() {
+                let (pos_x, pos_z) = layout.position(x, z);
+                y_vals.push(f(pos_x, pos_z));
+            }
+        }
+        y_vals
+    }
+
+    fn assert_normal(normals: &[f32], index: usize, expected: (f32, f32, f32), tolerance: f32) {
+        let size = (expected.0 * expected.0 + expected.1 * expected.1 + expected.2 * expected.2).sqrt();
+        let expected = [expected.0 / size, expected.1 / size, expected.2 / size];
+        let actual = &normals[3 * index..3 * index + 3];
+
+        for axis in 0..3 {
+            assert!(
+                (actual[axis] - expected[axis]).abs() < tolerance,
+                "normal {} was {:?}, expected {:?}", index, actual, expected,
+            );
+        }
+    }
+
+    #[test]
+    fn cpu_ripple_samples_the_grid_from_minus_one_to_one() {
+        let layout = GridLayout::default().with_resolution(4, 2).unwrap();
+        let time = 1234.0;
+        let y_vals = get_updated_3d_y_values(&layout, time);
+
+        assert_eq!(y_vals.len(), 15);
+        for z in 0..3 {
+            for x in 0..5 {
+                let (pos_x, pos_z) = (-1.0 + 0.5 * x as f32, -1.0 + z as f32);
+                assert_eq!(y_vals[5 * z + x], get_ripple_y(pos_x, pos_z, time), "point ({}, {})", x, z);
+            }
+        }
+
+        assert_eq!(get_ripple_y(0.0, 0.0, 0.0), 0.0);
+        assert!(y_vals.iter().all(|y| y.abs() <= RIPPLE_AMPLITUDE));
+    }
+
+    #[test]
+    fn transparent_triangles_sort_farthest_first_from_any_side() {
+        let (positions, indices) = get_position_cube();
+        // Front face at z = 1, back face at z = -1.
+        let front_face = &indices[0..6];
+        let back_face = &indices[6..12];
+
+        let mut looking_down_z = [0.0; 16];
+        looking_down_z[11] = -1.0;
+        looking_down_z[15] = 3.0;
+        let sorted = get_back_to_front_indices(&looking_down_z, &positions, &indices);
+        assert_eq!(&sorted[..6], back_face);
+        assert_eq!(&sorted[30..], front_face);
+
+        let mut looking_up_z = looking_down_z;
+        looking_up_z[11] = 1.0;
+        let sorted = get_back_to_front_indices(&looking_up_z, &positions, &indices);
+        assert_eq!(&sorted[..6], front_face);
+        assert_eq!(&sorted[30..], back_face);
+    }
+
+    #[test]
+    fn plane_normals_are_uniform_including_edges() {
+        let layout = GridLayout::default().with_resolution(7, 4).unwrap();
+        let y_vals = get_surface(&layout, |x, z| 0.5 * x - 0.25 * z);
+
+        for &shading in [Shading::Smooth, Shading::Flat].iter() {
+            let normals = get_grid_normals(&layout, &y_vals, shading);
+            for index in 0..layout.point_count() {
+                assert_normal(&normals, index, (-0.5, 1.0, 0.25), 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn smooth_normals_follow_paraboloid_gradient() {
+        let layout = GridLayout::default().with_resolution(40, 40).unwrap();
+        let y_vals = get_surface(&layout, |x, z| 0.5 * (x * x + z * z));
+        let normals = get_grid_normals(&layout, &y_vals, Shading::Smooth);
+
+        for z in 1..layout.rows {
+            for x in 1..layout.columns {
+                let (pos_x, pos_z) = layout.position(x, z);
+                assert_normal(&normals, z * layout.points_per_row() + x, (-pos_x, 1.0, -pos_z), 0.01);
+            }
+        }
+        assert_normal(&normals, 20 * layout.points_per_row() + 20, (0.0, 1.0, 0.0), 1e-5);
+    }
+
+    #[test]
+    fn smooth_normals_are_symmetric_on_cone() {
+        let layout = GridLayout::default().with_resolution(10, 10).unwrap();
+        let y_vals = get_surface(&layout, |x, z| -(x * x + z * z).sqrt());
+        let normals = get_grid_normals(&layout, &y_vals, Shading::Smooth);
+        let points_per_row = layout.points_per_row();
+
+        assert_normal(&normals, 5 * points_per_row + 5, (0.0, 1.0, 0.0), 1e-5);
+        for index in 0..layout.point_count() {
+            assert!(normals[3 * index + 1] > 0.0);
+        }
+
+        let corner = &normals[0..3];
+        let opposite = &normals[3 * (layout.point_count() - 1)..];
+        assert!((corner[0] + opposite[0]).abs() < 1e-5);
+        assert!((corner[1] - opposite[1]).abs() < 1e-5);
+        assert!((corner[2] + opposite[2]).abs() < 1e-5);
+    }
+
+    #[test]
+    fn depth_orders_points_without_moving_them_on_screen() {
+        let view = ViewTransform { rotation_x_axis: 0.0, rotation_y_axis: 0.0, zoom: 1.0, target: [0.0; 3] };
+        let matrices = get_3d_matrices(&ViewportParams {
+            bottom: 0.0,
+            top: 400.0,
+            left: 0.0,
+            right: 400.0,
+            canvas_height: 400.0,
+            canvas_width: 400.0,
+            view: &view,
+        });
+        let near = transform_point(&matrices.projection, 0.5, 0.25, 1.0);
+        let far = transform_point(&matrices.projection, 0.5, 0.25, -1.0);
+
+        assert_eq!(near[0] / near[3], far[0] / far[3]);
+        assert_eq!(near[1] / near[3], far[1] / far[3]);
+        assert!(near[2] / near[3] < far[2] / far[3]);
+        assert!(near[2] / near[3] > -1.0 && far[2] / far[3] < 1.0);
+    }
+
+    #[test]
+    fn drawing_buffer_follows_device_pixels() {
+        assert_eq!(get_drawing_buffer_size(600.0, 800.0, 1.0), (600, 800));
+        assert_eq!(get_drawing_buffer_size(600.0, 800.0, 2.0), (1200, 1600));
+        assert_eq!(get_drawing_buffer_size(333.0, 501.0, 1.5 * 0.5), (250, 376));
+        assert_eq!(get_drawing_buffer_size(0.0, 0.0, 2.0), (1, 1));
+    }
+}
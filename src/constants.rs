@@ -1,6 +1,10 @@
-pub const GRID_SIZE: usize = 200;
+pub const DEFAULT_GRID_SIZE: usize = 200;
+pub const MAX_GRID_VERTICES: usize = 65536; // vertices must fit u16 indices
 pub const FIELD_OF_VIEW: f32 = 45. * std::f32::consts::PI  / 180.0;
 pub const Z_FAR: f32 = 100.0;
 pub const Z_NEAR: f32 = 0.1;
 pub const Z_PLANE: f32 = -2.414213; // -1 / tan(pi/8)
-pub const FREQ: f32 = 3.0;
\ No newline at end of file
+pub const DEPTH_SCALE: f32 = 0.05; // view depth per model unit, written to clip z only
+pub const FREQ: f32 = 3.0;
+pub const RIPPLE_AMPLITUDE: f32 = 0.15;
+pub const RIPPLE_TIME_SCALE: f32 = 1.0 / 500.0;
\ No newline at end of file
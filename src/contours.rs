@@ -0,0 +1,153 @@
+use super::grid::GridLayout;
+
+const MAX_CONTOUR_LEVELS: usize = 256;
+
+fn interpolate(level: f32, a: (f32, f32, f32), b: (f32, f32, f32)) -> (f32, f32) {
+    let (ax, ay, az) = a;
+    let (bx, by, bz) = b;
+    let t = if by != ay { (level - ay) / (by - ay) } else { 0.5 };
+    (ax + t * (bx - ax), az + t * (bz - az))
+}
+
+/// Runs marching squares over the grid of `y_vals` laid out like
+/// `get_position_grid`, returning `GL::LINES` vertex pairs at every multiple
+/// of `interval`.
+pub fn get_contour_lines(layout: &GridLayout, y_vals: &[f32], interval: f32) -> Vec<f32> {
+    let mut lines: Vec<f32> = Vec::new();
+    if interval <= 0.0 || !interval.is_finite() || y_vals.len() < layout.point_count() {
+        return lines;
+    }
+
+    let (min, max) = y_vals.iter().fold((f32::MAX, f32::MIN), |(min, max), &value| {
+        (min.min(value), max.max(value))
+    });
+    let first_level = (min / interval).ceil() as i64;
+    let last_level = (max / interval).floor() as i64;
+    if last_level < first_level || (last_level - first_level) as usize >= MAX_CONTOUR_LEVELS {
+        return lines;
+    }
+
+    let points_per_row = layout.points_per_row();
+
+    for level_index in first_level..=last_level {
+        let level = level_index as f32 * interval;
+
+        for z in 0..layout.rows {
+            for x in 0..layout.columns {
+                let corner = |cx: usize, cz: usize| {
+                    let (pos_x, pos_z) = layout.position(cx, cz);
+                    (pos_x, y_vals[cz * points_per_row + cx], pos_z)
+                };
+
+                // Corners run anticlockwise from the cell origin.
+                let corners = [corner(x, z), corner(x + 1, z), corner(x + 1, z + 1), corner(x, z + 1)];
+                let case = corners
+                    .iter()
+                    .enumerate()
+                    .fold(0, |case, (i, c)| if c.1 >= level { case | 1 << i } else { case });
+
+                if case == 0 || case == 15 {
+                    continue;
+                }
+
+                let edge = |i: usize| interpolate(level, corners[i], corners[(i + 1) % 4]);
+                let mut push_segment = |from: usize, to: usize| {
+                    let (x1, z1) = edge(from);
+                    let (x2, z2) = edge(to);
+                    lines.extend_from_slice(&[x1, level, z1, x2, level, z2]);
+                };
+
+                // Edge i joins corner i to corner i + 1.
+                match case {
+                    1 | 14 => push_segment(3, 0),
+                    2 | 13 => push_segment(0, 1),
+                    3 | 12 => push_segment(3, 1),
+                    4 | 11 => push_segment(1, 2),
+                    6 | 9 => push_segment(0, 2),
+                    7 | 8 => push_segment(2, 3),
+                    5 | 10 => {
+                        let center = corners.iter().map(|c| c.1).sum::<f32>() / 4.0;
+                        if (center >= level) == (case == 5) {
+                            push_segment(3, 2);
+                            push_segment(0, 1);
+                        } else {
+                            push_segment(3, 0);
+                            push_segment(1, 2);
+                        }
+                    },
+                    _ => {},
+                }
+            }
+        }
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_cell() -> GridLayout {
+        GridLayout::default().with_resolution(1, 1).unwrap().with_extents(0.0, 1.0, 0.0, 1.0).unwrap()
+    }
+
+    // Corners in `y_vals` order: (0, 0), (1, 0), (0, 1), (1, 1).
+    fn assert_segments(lines: &[f32], expected: &[f32]) {
+        assert_eq!(lines.len(), expected.len(), "{:?}", lines);
+        for (actual, expected) in lines.iter().zip(expected.iter()) {
+            assert!((actual - expected).abs() < 1e-5, "{:?} != {:?}", lines, expected);
+        }
+    }
+
+    #[test]
+    fn single_raised_corner_cuts_across_it() {
+        let lines = get_contour_lines(&unit_cell(), &[0.8, 0.2, 0.2, 0.2], 0.5);
+        assert_segments(&lines, &[0.0, 0.5, 0.5, 0.5, 0.5, 0.0]);
+    }
+
+    #[test]
+    fn saddle_follows_the_cell_centre() {
+        // Centre 0.5 is above the level, so the raised corners join through it.
+        let lines = get_contour_lines(&unit_cell(), &[0.8, 0.2, 0.2, 0.8], 0.5);
+        assert_segments(&lines, &[
+            0.0, 0.5, 0.5, 0.5, 0.5, 1.0,
+            0.5, 0.5, 0.0, 1.0, 0.5, 0.5,
+        ]);
+
+        // Centre 0.4 is below, so each raised corner is cut off on its own.
+        let lines = get_contour_lines(&unit_cell(), &[0.6, 0.2, 0.2, 0.6], 0.5);
+        assert_segments(&lines, &[
+            0.0, 0.5, 0.25, 0.25, 0.5, 0.0,
+            1.0, 0.5, 0.75, 0.75, 0.5, 1.0,
+        ]);
+    }
+
+    #[test]
+    fn flat_grid_has_no_crossings() {
+        let layout = GridLayout::default().with_resolution(4, 3).unwrap();
+        assert!(get_contour_lines(&layout, &vec![0.3; layout.point_count()], 0.5).is_empty());
+        assert!(get_contour_lines(&layout, &vec![0.5; layout.point_count()], 0.5).is_empty());
+    }
+
+    #[test]
+    fn paraboloid_gives_one_segment_per_cell_and_level() {
+        let layout = GridLayout::default().with_resolution(2, 2).unwrap().with_extents(-1.0, 1.0, -1.0, 1.0).unwrap();
+        let mut y_vals = Vec::new();
+        for z in 0..3 {
+            for x in 0..3 {
+                let (pos_x, pos_z) = layout.position(x, z);
+                y_vals.push(0.9 * (pos_x * pos_x + pos_z * pos_z));
+            }
+        }
+
+        // Levels 0.5, 1.0 and 1.5 each cross all four cells once.
+        let lines = get_contour_lines(&layout, &y_vals, 0.5);
+        assert_eq!(lines.len(), 12 * 6);
+
+        for point in lines.chunks(3).filter(|point| point[1] == 0.5) {
+            let radius = (point[0] * point[0] + point[2] * point[2]).sqrt();
+            assert!((radius - 0.5 / 0.9).abs() < 1e-5, "{:?}", point);
+        }
+    }
+}
@@ -0,0 +1,524 @@
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+// Every level of parentheses, sign or exponent recurses, so pathological
+// input has to be cut off before it exhausts the wasm stack.
+const MAX_NESTING: usize = 128;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Variable {
+    X,
+    Z,
+    T,
+}
+
+#[derive(Clone, Copy)]
+enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    Power,
+}
+
+#[derive(Clone, Copy)]
+enum Function {
+    Abs,
+    Acos,
+    Asin,
+    Atan,
+    Atan2,
+    Ceil,
+    Cos,
+    Cosh,
+    Exp,
+    Floor,
+    Ln,
+    Log,
+    Max,
+    Min,
+    Pow,
+    Sign,
+    Sin,
+    Sinh,
+    Sqrt,
+    Tan,
+    Tanh,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "abs" => Function::Abs,
+            "acos" => Function::Acos,
+            "asin" => Function::Asin,
+            "atan" => Function::Atan,
+            "atan2" => Function::Atan2,
+            "ceil" => Function::Ceil,
+            "cos" => Function::Cos,
+            "cosh" => Function::Cosh,
+            "exp" => Function::Exp,
+            "floor" => Function::Floor,
+            "ln" => Function::Ln,
+            "log" => Function::Log,
+            "max" => Function::Max,
+            "min" => Function::Min,
+            "pow" => Function::Pow,
+            "sign" => Function::Sign,
+            "sin" => Function::Sin,
+            "sinh" => Function::Sinh,
+            "sqrt" => Function::Sqrt,
+            "tan" => Function::Tan,
+            "tanh" => Function::Tanh,
+            _ => return None,
+        })
+    }
+
+    fn arity(self) -> usize {
+        match self {
+            Function::Atan2 | Function::Max | Function::Min | Function::Pow => 2,
+            _ => 1,
+        }
+    }
+
+    fn apply(self, args: &[f32]) -> f32 {
+        let a = args[0];
+        match self {
+            Function::Abs => a.abs(),
+            Function::Acos => a.acos(),
+            Function::Asin => a.asin(),
+            Function::Atan => a.atan(),
+            Function::Atan2 => a.atan2(args[1]),
+            Function::Ceil => a.ceil(),
+            Function::Cos => a.cos(),
+            Function::Cosh => a.cosh(),
+            Function::Exp => a.exp(),
+            Function::Floor => a.floor(),
+            Function::Ln => a.ln(),
+            Function::Log => a.log10(),
+            Function::Max => a.max(args[1]),
+            Function::Min => a.min(args[1]),
+            Function::Pow => a.powf(args[1]),
+            Function::Sign => a.signum(),
+            Function::Sin => a.sin(),
+            Function::Sinh => a.sinh(),
+            Function::Sqrt => a.sqrt(),
+            Function::Tan => a.tan(),
+            Function::Tanh => a.tanh(),
+        }
+    }
+}
+
+enum Node {
+    Number(f32),
+    Variable(Variable),
+    Negate(Box<Node>),
+    Binary(BinaryOp, Box<Node>, Box<Node>),
+    Call(Function, Vec<Node>),
+}
+
+impl Node {
+    fn eval(&self, x: f32, z: f32, t: f32) -> f32 {
+        match self {
+            Node::Number(value) => *value,
+            Node::Variable(Variable::X) => x,
+            Node::Variable(Variable::Z) => z,
+            Node::Variable(Variable::T) => t,
+            Node::Negate(node) => -node.eval(x, z, t),
+            Node::Binary(op, lhs, rhs) => {
+                let lhs = lhs.eval(x, z, t);
+                let rhs = rhs.eval(x, z, t);
+                match op {
+                    BinaryOp::Add => lhs + rhs,
+                    BinaryOp::Subtract => lhs - rhs,
+                    BinaryOp::Multiply => lhs * rhs,
+                    BinaryOp::Divide => lhs / rhs,
+                    BinaryOp::Remainder => lhs % rhs,
+                    BinaryOp::Power => lhs.powf(rhs),
+                }
+            },
+            Node::Call(function, args) => {
+                let mut values = [0.0; 2];
+                for (value, arg) in values.iter_mut().zip(args.iter()) {
+                    *value = arg.eval(x, z, t);
+                }
+                function.apply(&values[..args.len()])
+            },
+        }
+    }
+
+    fn uses(&self, variable: Variable) -> bool {
+        match self {
+            Node::Number(_) => false,
+            Node::Variable(v) => *v == variable,
+            Node::Negate(node) => node.uses(variable),
+            Node::Binary(_, lhs, rhs) => lhs.uses(variable) || rhs.uses(variable),
+            Node::Call(_, args) => args.iter().any(|arg| arg.uses(variable)),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+enum Token {
+    Number(f32),
+    Ident(String),
+    Symbol(char),
+    End,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Number(value) => format!("number {}", value),
+            Token::Ident(name) => format!("'{}'", name),
+            Token::Symbol(symbol) => format!("'{}'", symbol),
+            Token::End => String::from("end of expression"),
+        }
+    }
+}
+
+struct Parser<'a> {
+    chars: Peekable<CharIndices<'a>>,
+    source: &'a str,
+    token: Token,
+    position: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str) -> Result<Self, String> {
+        let mut parser = Self {
+            chars: source.char_indices().peekable(),
+            source: source,
+            token: Token::End,
+            position: 0,
+            depth: 0,
+        };
+        parser.advance()?;
+        Ok(parser)
+    }
+
+    fn advance(&mut self) -> Result<(), String> {
+        while let Some(&(_, c)) = self.chars.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.chars.next();
+        }
+
+        let (start, c) = match self.chars.next() {
+            Some(next) => next,
+            None => {
+                self.position = self.source.len();
+                self.token = Token::End;
+                return Ok(());
+            },
+        };
+        self.position = start;
+
+        self.token = if c.is_ascii_digit() || c == '.' {
+            let mut end = start + c.len_utf8();
+            while let Some(&(i, c)) = self.chars.peek() {
+                if !(c.is_ascii_digit() || c == '.') {
+                    break;
+                }
+                end = i + c.len_utf8();
+                self.chars.next();
+            }
+            if let Some(exponent_end) = self.scan_exponent() {
+                end = exponent_end;
+            }
+            let text = &self.source[start..end];
+            let value = text
+                .parse::<f32>()
+                .map_err(|_| format!("Invalid number '{}' at position {}", text, start))?;
+            Token::Number(value)
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let mut end = start + c.len_utf8();
+            while let Some(&(i, c)) = self.chars.peek() {
+                if !(c.is_ascii_alphanumeric() || c == '_') {
+                    break;
+                }
+                end = i + c.len_utf8();
+                self.chars.next();
+            }
+            Token::Ident(self.source[start..end].to_lowercase())
+        } else if "+-*/%^(),".contains(c) {
+            Token::Symbol(c)
+        } else {
+            return Err(format!("Unexpected character '{}' at position {}", c, start));
+        };
+
+        Ok(())
+    }
+
+    /// Consumes an exponent such as `e-3` after a number's digits, returning
+    /// where it ends. A lone `e` is left alone so it still reads as a name.
+    fn scan_exponent(&mut self) -> Option<usize> {
+        let mut ahead = self.chars.clone();
+        match ahead.next() {
+            Some((_, 'e')) | Some((_, 'E')) => {},
+            _ => return None,
+        }
+        if let Some(&(_, '+')) | Some(&(_, '-')) = ahead.peek() {
+            ahead.next();
+        }
+        if !matches!(ahead.peek(), Some(&(_, c)) if c.is_ascii_digit()) {
+            return None;
+        }
+
+        let mut end = 0;
+        while let Some(&(i, c)) = ahead.peek() {
+            if !c.is_ascii_digit() {
+                break;
+            }
+            end = i + c.len_utf8();
+            ahead.next();
+        }
+        self.chars = ahead;
+        Some(end)
+    }
+
+    fn unexpected(&self) -> String {
+        format!("Unexpected {} at position {}", self.token.describe(), self.position)
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<(), String> {
+        if self.token != Token::Symbol(symbol) {
+            return Err(format!(
+                "Expected '{}' but found {} at position {}",
+                symbol,
+                self.token.describe(),
+                self.position,
+            ));
+        }
+        self.advance()
+    }
+
+    fn parse_expression(&mut self) -> Result<Node, String> {
+        let mut lhs = self.parse_term()?;
+        loop {
+            let op = match self.token {
+                Token::Symbol('+') => BinaryOp::Add,
+                Token::Symbol('-') => BinaryOp::Subtract,
+                _ => return Ok(lhs),
+            };
+            self.advance()?;
+            let rhs = self.parse_term()?;
+            lhs = Node::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+    }
+
+    fn parse_term(&mut self) -> Result<Node, String> {
+        let mut lhs = self.parse_unary()?;
+        loop {
+            let op = match self.token {
+                Token::Symbol('*') => BinaryOp::Multiply,
+                Token::Symbol('/') => BinaryOp::Divide,
+                Token::Symbol('%') => BinaryOp::Remainder,
+                _ => return Ok(lhs),
+            };
+            self.advance()?;
+            let rhs = self.parse_unary()?;
+            lhs = Node::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Node, String> {
+        if self.depth == MAX_NESTING {
+            return Err(format!("Expression is nested too deeply at position {}", self.position));
+        }
+
+        self.depth += 1;
+        let node = self.parse_signed();
+        self.depth -= 1;
+        node
+    }
+
+    fn parse_signed(&mut self) -> Result<Node, String> {
+        match self.token {
+            Token::Symbol('-') => {
+                self.advance()?;
+                Ok(Node::Negate(Box::new(self.parse_unary()?)))
+            },
+            Token::Symbol('+') => {
+                self.advance()?;
+                self.parse_unary()
+            },
+            _ => self.parse_power(),
+        }
+    }
+
+    fn parse_power(&mut self) -> Result<Node, String> {
+        let base = self.parse_atom()?;
+        if self.token == Token::Symbol('^') {
+            self.advance()?;
+            let exponent = self.parse_unary()?;
+            return Ok(Node::Binary(BinaryOp::Power, Box::new(base), Box::new(exponent)));
+        }
+        Ok(base)
+    }
+
+    fn parse_atom(&mut self) -> Result<Node, String> {
+        match self.token.clone() {
+            Token::Number(value) => {
+                self.advance()?;
+                Ok(Node::Number(value))
+            },
+            Token::Symbol('(') => {
+                self.advance()?;
+                let node = self.parse_expression()?;
+                self.expect(')')?;
+                Ok(node)
+            },
+            Token::Ident(name) => {
+                let position = self.position;
+                self.advance()?;
+
+                if self.token == Token::Symbol('(') {
+                    return self.parse_call(&name, position);
+                }
+
+                match name.as_str() {
+                    "x" => Ok(Node::Variable(Variable::X)),
+                    "z" => Ok(Node::Variable(Variable::Z)),
+                    "t" => Ok(Node::Variable(Variable::T)),
+                    "pi" => Ok(Node::Number(std::f32::consts::PI)),
+                    "e" => Ok(Node::Number(std::f32::consts::E)),
+                    _ => Err(format!("Unknown variable '{}' at position {}", name, position)),
+                }
+            },
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn parse_call(&mut self, name: &str, position: usize) -> Result<Node, String> {
+        let function = Function::from_name(name)
+            .ok_or_else(|| format!("Unknown function '{}' at position {}", name, position))?;
+
+        self.expect('(')?;
+        let mut args = vec![self.parse_expression()?];
+        while self.token == Token::Symbol(',') {
+            self.advance()?;
+            args.push(self.parse_expression()?);
+        }
+        self.expect(')')?;
+
+        if args.len() != function.arity() {
+            return Err(format!(
+                "'{}' takes {} argument(s) but was given {} at position {}",
+                name,
+                function.arity(),
+                args.len(),
+                position,
+            ));
+        }
+
+        Ok(Node::Call(function, args))
+    }
+}
+
+/// A parsed surface expression in `x`, `z` and time `t`, e.g.
+/// `sin(sqrt(x*x + z*z) + t)`.
+pub struct Expression {
+    root: Node,
+}
+
+impl Expression {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut parser = Parser::new(source)?;
+        if parser.token == Token::End {
+            return Err(String::from("Expression is empty"));
+        }
+
+        let root = parser.parse_expression()?;
+        if parser.token != Token::End {
+            return Err(parser.unexpected());
+        }
+
+        Ok(Self { root: root })
+    }
+
+    pub fn eval(&self, x: f32, z: f32, t: f32) -> f32 {
+        self.root.eval(x, z, t)
+    }
+
+    pub fn uses_time(&self) -> bool {
+        self.root.uses(Variable::T)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(source: &str, x: f32) -> f32 {
+        Expression::parse(source).unwrap().eval(x, 0.0, 0.0)
+    }
+
+    fn error(source: &str) -> String {
+        match Expression::parse(source) {
+            Ok(_) => panic!("'{}' should not parse", source),
+            Err(err) => err,
+        }
+    }
+
+    #[test]
+    fn operators_follow_precedence() {
+        assert_eq!(eval("-x^2", 2.0), -4.0);
+        assert_eq!(eval("2^3^2", 0.0), 512.0);
+        assert_eq!(eval("2^-1", 0.0), 0.5);
+        assert_eq!(eval("1 + 2 * 3", 0.0), 7.0);
+        assert_eq!(eval("(1 + 2) * 3", 0.0), 9.0);
+        assert_eq!(eval("10 - 4 - 3", 0.0), 3.0);
+        assert_eq!(eval("10 % 4 / 2", 0.0), 1.0);
+        assert_eq!(eval("--x", 3.0), 3.0);
+        assert_eq!(eval("max(x, 1) + min(2, pow(2, 3))", 5.0), 7.0);
+    }
+
+    #[test]
+    fn numbers_take_exponents() {
+        assert_eq!(eval("1e-3", 0.0), 0.001);
+        assert_eq!(eval("2.5E2", 0.0), 250.0);
+        assert_eq!(eval("1e+2 * x", 2.0), 200.0);
+        assert_eq!(eval("2 * e", 0.0), 2.0 * std::f32::consts::E);
+        assert_eq!(error("2e"), "Unexpected 'e' at position 1");
+        assert_eq!(error("2e-"), "Unexpected 'e' at position 1");
+    }
+
+    #[test]
+    fn calls_check_names_and_arity() {
+        assert_eq!(error("max(1)"), "'max' takes 2 argument(s) but was given 1 at position 0");
+        assert_eq!(error("x + sin(1, 2)"), "'sin' takes 1 argument(s) but was given 2 at position 4");
+        assert_eq!(error("foo(x)"), "Unknown function 'foo' at position 0");
+        assert_eq!(error("x + y"), "Unknown variable 'y' at position 4");
+    }
+
+    #[test]
+    fn errors_report_positions() {
+        assert_eq!(error(""), "Expression is empty");
+        assert_eq!(error("x + * 2"), "Unexpected '*' at position 4");
+        assert_eq!(error("sin(x"), "Expected ')' but found end of expression at position 5");
+        assert_eq!(error("x $ 2"), "Unexpected character '$' at position 2");
+        assert_eq!(error("x 2"), "Unexpected number 2 at position 2");
+        assert_eq!(error("1.2.3"), "Invalid number '1.2.3' at position 0");
+    }
+
+    #[test]
+    fn deep_nesting_is_rejected_instead_of_overflowing() {
+        let nested = |depth: usize| format!("{}x{}", "(".repeat(depth), ")".repeat(depth));
+        assert_eq!(eval(&nested(50), 2.0), 2.0);
+
+        assert!(error(&nested(10000)).starts_with("Expression is nested too deeply"));
+        assert!(error(&format!("{}1", "-".repeat(10000))).starts_with("Expression is nested too deeply"));
+        assert!(error(&"2^".repeat(10000)).starts_with("Expression is nested too deeply"));
+    }
+
+    #[test]
+    fn reports_whether_time_is_used() {
+        assert!(Expression::parse("sin(sqrt(x*x + z*z) + t)").unwrap().uses_time());
+        assert!(Expression::parse("max(x, -t)").unwrap().uses_time());
+        assert!(!Expression::parse("x * z + pi").unwrap().uses_time());
+    }
+}
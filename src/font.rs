@@ -0,0 +1,399 @@
+use std::collections::HashMap;
+use super::glyph_atlas;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FontKind {
+    Bitmap,
+    /// Single-channel signed distance field, where `distance_range` atlas
+    /// pixels span the full 0 to 1 range.
+    Sdf { distance_range: f32 },
+}
+
+/// Metrics in atlas pixels. Offsets run right and down from the pen position
+/// at the top of the line.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Glyph {
+    pub uvs: (f32, f32, f32, f32),
+    pub width: f32,
+    pub height: f32,
+    pub x_offset: f32,
+    pub y_offset: f32,
+    pub advance: f32,
+}
+
+/// One glyph's screen rectangle in pixels, with y increasing upwards.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GlyphQuad {
+    pub bottom: f32,
+    pub top: f32,
+    pub left: f32,
+    pub right: f32,
+    pub uvs: (f32, f32, f32, f32),
+}
+
+pub struct Font {
+    pub kind: FontKind,
+    pub line_height: f32,
+    pub atlas_width: usize,
+    pub atlas_height: usize,
+    pub atlas_pixels: Vec<u8>,
+    glyphs: HashMap<char, Glyph>,
+    kerning: HashMap<(char, char), f32>,
+}
+
+fn parse_pairs(line: &str) -> (&str, HashMap<&str, &str>) {
+    let line = line.trim();
+    let (tag, mut rest) = match line.find(char::is_whitespace) {
+        Some(end) => (&line[..end], line[end..].trim_start()),
+        None => (line, ""),
+    };
+
+    let mut pairs = HashMap::new();
+    while let Some(equals) = rest.find('=') {
+        let key = rest[..equals].trim();
+        let after = &rest[equals + 1..];
+        let (value, remaining) = if let Some(quoted) = after.strip_prefix('"') {
+            let end = quoted.find('"').unwrap_or(quoted.len());
+            (&quoted[..end], quoted.get(end + 1..).unwrap_or(""))
+        } else {
+            let end = after.find(char::is_whitespace).unwrap_or(after.len());
+            (&after[..end], &after[end..])
+        };
+
+        pairs.insert(key, value);
+        rest = remaining.trim_start();
+    }
+
+    (tag, pairs)
+}
+
+fn get_number(pairs: &HashMap<&str, &str>, key: &str, line_number: usize) -> Result<f32, String> {
+    let value = pairs.get(key).ok_or(format!("Line {}: missing {}", line_number, key))?;
+    value.parse().map_err(|_| format!("Line {}: invalid {} {:?}", line_number, key, value))
+}
+
+fn get_char(pairs: &HashMap<&str, &str>, key: &str, line_number: usize) -> Result<char, String> {
+    let code = get_number(pairs, key, line_number)? as u32;
+    std::char::from_u32(code).ok_or(format!("Line {}: invalid character code {}", line_number, code))
+}
+
+fn decode_atlas_png(bytes: &[u8]) -> Result<(usize, usize, Vec<u8>), String> {
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let (info, mut reader) = decoder
+        .read_info()
+        .map_err(|err| format!("Unable to read PNG: {}", err))?;
+
+    let mut buf = vec![0; info.buffer_size()];
+    reader
+        .next_frame(&mut buf)
+        .map_err(|err| format!("Unable to decode PNG: {}", err))?;
+
+    let width = info.width as usize;
+    let height = info.height as usize;
+    let samples = info.color_type.samples();
+    let mut pixels = Vec::with_capacity(4 * width * height);
+
+    for row in buf.chunks(info.line_size).take(height) {
+        for pixel in row.chunks(samples).take(width) {
+            // Glyph coverage, or distance for SDF atlases, ends up in alpha.
+            let alpha = match info.color_type {
+                png::ColorType::RGBA => pixel[3],
+                png::ColorType::GrayscaleAlpha => pixel[1],
+                _ => pixel[0],
+            };
+            pixels.extend_from_slice(&[255, 255, 255, alpha]);
+        }
+    }
+
+    Ok((width, height, pixels))
+}
+
+impl Font {
+    /// The 5x7 font from `glyph_atlas`, one pixel of leading above each line.
+    pub fn builtin() -> Self {
+        let mut glyphs = HashMap::new();
+        for code in 0x20..0x7f_u8 {
+            let c = code as char;
+            glyphs.insert(c, Glyph {
+                uvs: glyph_atlas::get_glyph_uvs(c),
+                width: glyph_atlas::GLYPH_WIDTH as f32,
+                height: glyph_atlas::GLYPH_HEIGHT as f32,
+                x_offset: 0.0,
+                y_offset: glyph_atlas::LINE_HEIGHT - glyph_atlas::GLYPH_HEIGHT as f32,
+                advance: glyph_atlas::GLYPH_ADVANCE,
+            });
+        }
+
+        Self {
+            kind: FontKind::Bitmap,
+            line_height: glyph_atlas::LINE_HEIGHT,
+            atlas_width: glyph_atlas::ATLAS_WIDTH,
+            atlas_height: glyph_atlas::ATLAS_HEIGHT,
+            atlas_pixels: glyph_atlas::get_atlas_pixels(),
+            glyphs: glyphs,
+            kerning: HashMap::new(),
+        }
+    }
+
+    /// Reads an AngelCode BMFont text descriptor and its single atlas page.
+    /// Descriptors with a `distanceField` line are treated as SDF fonts unless
+    /// `distance_range` overrides it.
+    pub fn from_bmfont(descriptor: &str, atlas_png: &[u8], distance_range: Option<f32>) -> Result<Self, String> {
+        let (atlas_width, atlas_height, atlas_pixels) = decode_atlas_png(atlas_png)?;
+        let mut font = Self::from_bmfont_descriptor(descriptor, atlas_width, atlas_height)?;
+        font.atlas_pixels = atlas_pixels;
+
+        if let Some(distance_range) = distance_range {
+            if !(distance_range > 0.0 && distance_range.is_finite()) {
+                return Err(format!("SDF distance range must be positive, got {}", distance_range));
+            }
+            font.kind = FontKind::Sdf { distance_range: distance_range };
+        }
+        Ok(font)
+    }
+
+    fn from_bmfont_descriptor(descriptor: &str, atlas_width: usize, atlas_height: usize) -> Result<Self, String> {
+        let mut font = Self {
+            kind: FontKind::Bitmap,
+            line_height: 0.0,
+            atlas_width: atlas_width,
+            atlas_height: atlas_height,
+            atlas_pixels: Vec::new(),
+            glyphs: HashMap::new(),
+            kerning: HashMap::new(),
+        };
+
+        for (index, line) in descriptor.lines().enumerate() {
+            let line_number = index + 1;
+            let (tag, pairs) = parse_pairs(line);
+
+            match tag {
+                "common" => {
+                    font.line_height = get_number(&pairs, "lineHeight", line_number)?;
+                    if get_number(&pairs, "pages", line_number).unwrap_or(1.0) > 1.0 {
+                        return Err(String::from("Fonts with more than one atlas page are not supported"));
+                    }
+                    let scale_w = get_number(&pairs, "scaleW", line_number)? as usize;
+                    let scale_h = get_number(&pairs, "scaleH", line_number)? as usize;
+                    if (scale_w, scale_h) != (atlas_width, atlas_height) {
+                        return Err(format!(
+                            "Atlas is {}x{} but the descriptor expects {}x{}",
+                            atlas_width, atlas_height, scale_w, scale_h
+                        ));
+                    }
+                },
+                "char" => {
+                    let c = get_char(&pairs, "id", line_number)?;
+                    let x = get_number(&pairs, "x", line_number)?;
+                    let y = get_number(&pairs, "y", line_number)?;
+                    let width = get_number(&pairs, "width", line_number)?;
+                    let height = get_number(&pairs, "height", line_number)?;
+
+                    font.glyphs.insert(c, Glyph {
+                        uvs: (
+                            x / atlas_width as f32,
+                            y / atlas_height as f32,
+                            (x + width) / atlas_width as f32,
+                            (y + height) / atlas_height as f32,
+                        ),
+                        width: width,
+                        height: height,
+                        x_offset: get_number(&pairs, "xoffset", line_number)?,
+                        y_offset: get_number(&pairs, "yoffset", line_number)?,
+                        advance: get_number(&pairs, "xadvance", line_number)?,
+                    });
+                },
+                "kerning" => {
+                    let pair = (get_char(&pairs, "first", line_number)?, get_char(&pairs, "second", line_number)?);
+                    font.kerning.insert(pair, get_number(&pairs, "amount", line_number)?);
+                },
+                "distanceField" => {
+                    font.kind = FontKind::Sdf { distance_range: get_number(&pairs, "distanceRange", line_number)? };
+                },
+                _ => {},
+            }
+        }
+
+        if font.line_height <= 0.0 || font.glyphs.is_empty() {
+            return Err(String::from("Font descriptor has no common line or no characters"));
+        }
+        Ok(font)
+    }
+
+    fn get_glyph(&self, c: char) -> Option<&Glyph> {
+        self.glyphs.get(&c).or_else(|| self.glyphs.get(&'?'))
+    }
+
+    pub fn get_kerning(&self, previous: char, c: char) -> f32 {
+        self.kerning.get(&(previous, c)).cloned().unwrap_or(0.0)
+    }
+
+    /// Pen advance across one line of text, in atlas pixels.
+    fn get_line_width(&self, line: &str) -> f32 {
+        let mut width = 0.0;
+        let mut previous = None;
+        for c in line.chars() {
+            if let Some(previous) = previous {
+                width += self.get_kerning(previous, c);
+            }
+            width += self.get_glyph(c).map_or(0.0, |glyph| glyph.advance);
+            previous = Some(c);
+        }
+        width
+    }
+
+    pub fn measure(&self, text: &str, scale: f32) -> f32 {
+        text.lines().map(|line| self.get_line_width(line)).fold(0.0, f32::max) * scale
+    }
+
+    /// Splits `text` at newlines and then greedily at spaces so no line is
+    /// wider than `max_width` atlas pixels, breaking words that cannot fit.
+    pub fn wrap(&self, text: &str, max_width: Option<f32>) -> Vec<String> {
+        let mut lines = Vec::new();
+
+        for paragraph in text.split('\n') {
+            let max_width = match max_width {
+                Some(max_width) => max_width,
+                None => {
+                    lines.push(paragraph.to_string());
+                    continue;
+                },
+            };
+
+            let mut line = String::new();
+            for word in paragraph.split(' ') {
+                let candidate = if line.is_empty() { word.to_string() } else { format!("{} {}", line, word) };
+                if self.get_line_width(&candidate) <= max_width {
+                    line = candidate;
+                    continue;
+                }
+
+                if !line.is_empty() {
+                    lines.push(std::mem::take(&mut line));
+                }
+                for c in word.chars() {
+                    line.push(c);
+                    if self.get_line_width(&line) > max_width && line.chars().count() > 1 {
+                        line.pop();
+                        lines.push(std::mem::replace(&mut line, c.to_string()));
+                    }
+                }
+            }
+            lines.push(line);
+        }
+
+        lines
+    }
+
+    /// Visible glyphs placed in pixel space, with the first line's top at
+    /// `top` and y increasing upwards.
+    pub fn glyph_quads(&self, text: &str, left: f32, top: f32, scale: f32, max_width: Option<f32>) -> Vec<GlyphQuad> {
+        let lines = self.wrap(text, max_width.map(|width| width / scale));
+        let mut quads = Vec::new();
+
+        for (line_index, line) in lines.iter().enumerate() {
+            let line_top = top - line_index as f32 * self.line_height * scale;
+            let mut pen = 0.0;
+            let mut previous = None;
+
+            for c in line.chars() {
+                if let Some(previous) = previous {
+                    pen += self.get_kerning(previous, c);
+                }
+                previous = Some(c);
+
+                let glyph = match self.get_glyph(c) {
+                    Some(glyph) => glyph,
+                    None => continue,
+                };
+
+                if glyph.width > 0.0 && glyph.height > 0.0 && c != ' ' {
+                    let left = left + (pen + glyph.x_offset) * scale;
+                    let top = line_top - glyph.y_offset * scale;
+                    quads.push(GlyphQuad {
+                        bottom: top - glyph.height * scale,
+                        top: top,
+                        left: left,
+                        right: left + glyph.width * scale,
+                        uvs: glyph.uvs,
+                    });
+                }
+                pen += glyph.advance;
+            }
+        }
+
+        quads
+    }
+
+    /// Edge softness for an SDF font drawn at `scale`, in distance units.
+    pub fn get_smoothing(&self, scale: f32) -> f32 {
+        match self.kind {
+            FontKind::Bitmap => 0.0,
+            FontKind::Sdf { distance_range } => 0.5 / (distance_range * scale),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DESCRIPTOR: &str = r#"info face="Test Sans" size=16 bold=0
+common lineHeight=20 base=16 scaleW=64 scaleH=32 pages=1
+page id=0 file="test.png"
+chars count=4
+char id=32 x=0 y=0 width=0 height=0 xoffset=0 yoffset=0 xadvance=5 page=0
+char id=63 x=0 y=0 width=8 height=12 xoffset=0 yoffset=4 xadvance=9 page=0
+char id=65 x=8 y=0 width=10 height=12 xoffset=-1 yoffset=4 xadvance=10 page=0
+char id=86 x=18 y=0 width=10 height=12 xoffset=0 yoffset=4 xadvance=10 page=0
+kernings count=1
+kerning first=65 second=86 amount=-2
+distanceField fieldType=sdf distanceRange=4
+"#;
+
+    #[test]
+    fn parses_bmfont_descriptors() {
+        let font = Font::from_bmfont_descriptor(DESCRIPTOR, 64, 32).unwrap();
+
+        assert_eq!(font.kind, FontKind::Sdf { distance_range: 4.0 });
+        assert_eq!(font.line_height, 20.0);
+        assert_eq!(font.get_glyph('A').unwrap().uvs, (0.125, 0.0, 18.0 / 64.0, 0.375));
+        assert_eq!(font.get_kerning('A', 'V'), -2.0);
+        assert_eq!(font.get_glyph('Z'), font.get_glyph('?'));
+        assert!(Font::from_bmfont_descriptor(DESCRIPTOR, 128, 32).is_err());
+        assert!(Font::from_bmfont_descriptor("char id=65 x=oops", 64, 32).is_err());
+    }
+
+    #[test]
+    fn kerning_tightens_pairs() {
+        let font = Font::from_bmfont_descriptor(DESCRIPTOR, 64, 32).unwrap();
+
+        assert_eq!(font.measure("AV", 1.0), 18.0);
+        assert_eq!(font.measure("VA", 2.0), 40.0);
+
+        let quads = font.glyph_quads("AV", 100.0, 50.0, 1.0, None);
+        assert_eq!((quads[0].left, quads[0].top), (99.0, 46.0));
+        assert_eq!(quads[1].left, 108.0);
+    }
+
+    #[test]
+    fn wraps_at_spaces_and_breaks_long_words() {
+        let font = Font::builtin();
+
+        assert_eq!(font.wrap("one two three", Some(48.0)), vec!["one two", "three"]);
+        assert_eq!(font.wrap("abcdefghij", Some(24.0)), vec!["abcd", "efgh", "ij"]);
+        assert_eq!(font.wrap("a\nb c", None), vec!["a", "b c"]);
+
+        let quads = font.glyph_quads("one two three", 0.0, 100.0, 2.0, Some(96.0));
+        assert_eq!(quads.len(), 11);
+        assert_eq!(quads.last().unwrap().bottom, 100.0 - 2.0 * 2.0 * glyph_atlas::LINE_HEIGHT);
+    }
+
+    #[test]
+    fn builtin_glyphs_sit_on_the_line_bottom() {
+        let quad = Font::builtin().glyph_quads("A", 10.0, 16.0, 2.0, None)[0];
+
+        assert_eq!((quad.left, quad.top, quad.bottom), (10.0, 14.0, 0.0));
+    }
+}
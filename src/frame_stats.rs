@@ -0,0 +1,81 @@
+use wasm_bindgen::prelude::*;
+use web_sys::*;
+use super::buffer_pool::BufferStats;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Phase {
+    Update,
+    Scene,
+    Overlay,
+    Hud,
+}
+
+/// Counters for one animation frame, with CPU times in milliseconds.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FrameStats {
+    pub frame_ms: f32,
+    pub update_ms: f32,
+    pub scene_ms: f32,
+    pub overlay_ms: f32,
+    pub hud_ms: f32,
+    pub ticks: u32,
+    pub draw_calls: usize,
+    pub triangles: usize,
+    pub bytes_uploaded: usize,
+}
+
+pub fn now() -> f64 {
+    window().and_then(|window| window.performance()).map_or(0.0, |performance| performance.now())
+}
+
+/// Collects `FrameStats` as a frame runs; `last` holds the latest complete
+/// frame.
+pub struct FrameProfiler {
+    pub last: FrameStats,
+    current: FrameStats,
+    last_frame_time: Option<f64>,
+    phase_start: f64,
+}
+
+impl FrameProfiler {
+    pub fn new() -> Self {
+        Self {
+            last: FrameStats::default(),
+            current: FrameStats::default(),
+            last_frame_time: None,
+            phase_start: 0.0,
+        }
+    }
+
+    pub fn begin_frame(&mut self, frame_time: f64, ticks: u32) {
+        self.current = FrameStats {
+            frame_ms: (frame_time - self.last_frame_time.unwrap_or(frame_time)) as f32,
+            ticks: ticks,
+            ..FrameStats::default()
+        };
+        self.last_frame_time = Some(frame_time);
+    }
+
+    pub fn begin_phase(&mut self) {
+        self.phase_start = now();
+    }
+
+    pub fn end_phase(&mut self, phase: Phase) {
+        let elapsed = (now() - self.phase_start) as f32;
+        let total = match phase {
+            Phase::Update => &mut self.current.update_ms,
+            Phase::Scene => &mut self.current.scene_ms,
+            Phase::Overlay => &mut self.current.overlay_ms,
+            Phase::Hud => &mut self.current.hud_ms,
+        };
+        *total += elapsed;
+    }
+
+    pub fn end_frame(&mut self, buffer_stats: &BufferStats) {
+        self.current.draw_calls = buffer_stats.draw_calls;
+        self.current.triangles = buffer_stats.triangles;
+        self.current.bytes_uploaded = buffer_stats.bytes_uploaded;
+        self.last = self.current;
+    }
+}
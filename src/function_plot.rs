@@ -0,0 +1,139 @@
+use super::expression::Expression;
+use super::grid::{is_valid_range, GridLayout};
+use super::heightmap::Heightmap;
+
+enum SurfaceSource {
+    Expression(Expression),
+    Heightmap(Heightmap),
+}
+
+pub struct FunctionPlot {
+    source: Option<SurfaceSource>,
+    pub x_min: f32,
+    pub x_max: f32,
+    pub z_min: f32,
+    pub z_max: f32,
+    pub y_scale: f32,
+    layout: GridLayout,
+    y_vals: Vec<f32>,
+    dirty: bool,
+}
+
+impl FunctionPlot {
+    pub fn new() -> Self {
+        Self {
+            source: None,
+            x_min: -1.0,
+            x_max: 1.0,
+            z_min: -1.0,
+            z_max: 1.0,
+            y_scale: 1.0,
+            layout: GridLayout::default(),
+            y_vals: Vec::new(),
+            dirty: true,
+        }
+    }
+
+    pub fn set_expression(&mut self, source: &str) -> Result<(), String> {
+        self.source = Some(SurfaceSource::Expression(Expression::parse(source)?));
+        self.dirty = true;
+        Ok(())
+    }
+
+    pub fn set_heightmap(&mut self, heightmap: Heightmap) {
+        self.source = Some(SurfaceSource::Heightmap(heightmap));
+        self.dirty = true;
+    }
+
+    pub fn clear(&mut self) {
+        self.source = None;
+        self.y_vals = Vec::new();
+    }
+
+    pub fn set_domain(&mut self, x_min: f32, x_max: f32, z_min: f32, z_max: f32) -> Result<(), String> {
+        if !is_valid_range(x_min, x_max) || !is_valid_range(z_min, z_max) {
+            return Err(format!(
+                "Invalid plot domain x: [{}, {}], z: [{}, {}]",
+                x_min, x_max, z_min, z_max,
+            ));
+        }
+
+        self.x_min = x_min;
+        self.x_max = x_max;
+        self.z_min = z_min;
+        self.z_max = z_max;
+        self.dirty = true;
+        Ok(())
+    }
+
+    pub fn set_y_scale(&mut self, y_scale: f32) {
+        self.y_scale = y_scale;
+        self.dirty = true;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.source.is_some()
+    }
+
+    /// Samples the surface onto `layout`, recomputing only when the source,
+    /// domain or layout changed or the expression depends on time.
+    pub fn update(&mut self, curr_time: f32, layout: &GridLayout) {
+        if *layout != self.layout {
+            self.layout = *layout;
+            self.dirty = true;
+        }
+
+        match &self.source {
+            Some(SurfaceSource::Expression(expression)) => {
+                if self.dirty || expression.uses_time() {
+                    self.y_vals = self.get_expression_y_values(expression, curr_time);
+                }
+            },
+            Some(SurfaceSource::Heightmap(heightmap)) => {
+                if self.dirty {
+                    self.y_vals = self.get_heightmap_y_values(heightmap);
+                }
+            },
+            None => return,
+        }
+
+        self.dirty = false;
+    }
+
+    fn get_expression_y_values(&self, expression: &Expression, curr_time: f32) -> Vec<f32> {
+        let points_per_row = self.layout.points_per_row();
+        let x_step = (self.x_max - self.x_min) / self.layout.columns as f32;
+        let z_step = (self.z_max - self.z_min) / self.layout.rows as f32;
+        let t = curr_time / 1000.0;
+
+        let mut y_vals = vec![0.0; self.layout.point_count()];
+        for z in 0..self.layout.rows + 1 {
+            for x in 0..points_per_row {
+                let x_val = self.x_min + x as f32 * x_step;
+                let z_val = self.z_min + z as f32 * z_step;
+                let y_val = self.y_scale * expression.eval(x_val, z_val, t);
+                y_vals[z * points_per_row + x] = if y_val.is_finite() { y_val } else { 0.0 };
+            }
+        }
+
+        y_vals
+    }
+
+    fn get_heightmap_y_values(&self, heightmap: &Heightmap) -> Vec<f32> {
+        let (min, max) = heightmap.get_range();
+        let range = if max > min { max - min } else { 1.0 };
+
+        heightmap
+            .resample(self.layout.columns, self.layout.rows)
+            .iter()
+            .map(|value| self.y_scale * ((value - min) / range - 0.5))
+            .collect()
+    }
+
+    pub fn y_vals(&self) -> Option<&Vec<f32>> {
+        match self.source {
+            Some(_) => Some(&self.y_vals),
+            None => None,
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use wasm_bindgen::JsCast;
+use wasm_bindgen::JsValue;
+use wasm_bindgen::prelude::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+use web_sys::*;
+
+const DEFAULT_TICK_RATE: f32 = 60.0;
+const MAX_TICK_RATE: f32 = 240.0;
+const MAX_FRAME_MS: f64 = 250.0;
+const MAX_TICKS_PER_FRAME: u32 = 8;
+
+/// Holds the frame closure so it can request the next frame with itself.
+pub type FrameCallback = Rc<RefCell<Option<Closure<dyn FnMut(f64)>>>>;
+
+/// What one animation frame should do: run `ticks` simulation steps, then
+/// render `alpha` of the way from the previous tick's state to the latest.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frame {
+    pub ticks: u32,
+    pub alpha: f32,
+}
+
+/// Turns animation frame timestamps into whole simulation ticks of a fixed
+/// length, carrying the remainder over to the next frame.
+pub struct FixedStep {
+    tick_ms: f64,
+    accumulator: f64,
+    last_frame_time: Option<f64>,
+    paused: bool,
+    pending_steps: u32,
+    time: f64,
+}
+
+impl FixedStep {
+    pub fn new() -> Self {
+        Self {
+            tick_ms: 1000.0 / DEFAULT_TICK_RATE as f64,
+            accumulator: 0.0,
+            last_frame_time: None,
+            paused: false,
+            pending_steps: 0,
+            time: 0.0,
+        }
+    }
+
+    pub fn get_tick_rate(&self) -> f32 {
+        (1000.0 / self.tick_ms) as f32
+    }
+
+    pub fn set_tick_rate(&mut self, ticks_per_second: f32) -> Result<(), String> {
+        if !(1.0..=MAX_TICK_RATE).contains(&ticks_per_second) {
+            return Err(format!("Tick rate must be between 1 and {}, got {}", MAX_TICK_RATE, ticks_per_second));
+        }
+
+        self.tick_ms = 1000.0 / ticks_per_second as f64;
+        self.accumulator = self.accumulator.min(self.tick_ms);
+        Ok(())
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+        self.pending_steps = 0;
+        self.accumulator = 0.0;
+    }
+
+    /// Queues one tick for the next frame while paused.
+    pub fn step(&mut self) {
+        if self.paused {
+            self.pending_steps += 1;
+        }
+    }
+
+    /// Long gaps, e.g. a backgrounded tab, are clamped and at most
+    /// `MAX_TICKS_PER_FRAME` ticks run per frame so the simulation never
+    /// tries to catch up all at once. Paused frames show the latest tick.
+    pub fn advance(&mut self, frame_time: f64) -> Frame {
+        let elapsed = frame_time - self.last_frame_time.unwrap_or(frame_time);
+        self.last_frame_time = Some(frame_time);
+
+        if self.paused {
+            let ticks = self.pending_steps.min(MAX_TICKS_PER_FRAME);
+            self.pending_steps -= ticks;
+            return Frame { ticks: ticks, alpha: 1.0 };
+        }
+
+        self.accumulator += elapsed.clamp(0.0, MAX_FRAME_MS);
+        let ticks = ((self.accumulator / self.tick_ms) as u32).min(MAX_TICKS_PER_FRAME);
+        self.accumulator = (self.accumulator - ticks as f64 * self.tick_ms).min(self.tick_ms);
+
+        Frame {
+            ticks: ticks,
+            alpha: (self.accumulator / self.tick_ms) as f32,
+        }
+    }
+
+    /// Advances simulation time by one tick and returns it in milliseconds.
+    pub fn tick(&mut self) -> f32 {
+        self.time += self.tick_ms;
+        self.time as f32
+    }
+}
+
+pub fn request_animation_frame(callback: &Closure<dyn FnMut(f64)>) -> Result<i32, JsValue> {
+    window().unwrap().request_animation_frame(callback.as_ref().unchecked_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn carries_partial_ticks_between_frames() {
+        let mut timer = FixedStep::new();
+        timer.set_tick_rate(50.0).unwrap();
+
+        assert_eq!(timer.advance(1000.0), Frame { ticks: 0, alpha: 0.0 });
+        assert_eq!(timer.advance(1030.0), Frame { ticks: 1, alpha: 0.5 });
+        assert_eq!(timer.advance(1040.0), Frame { ticks: 1, alpha: 0.0 });
+        assert_eq!(timer.advance(1045.0).ticks, 0);
+        assert_eq!(timer.advance(10000.0).ticks, MAX_TICKS_PER_FRAME);
+    }
+
+    #[test]
+    fn paused_timer_only_runs_requested_steps() {
+        let mut timer = FixedStep::new();
+        timer.advance(0.0);
+        timer.pause();
+        timer.step();
+        timer.step();
+
+        assert_eq!(timer.advance(500.0), Frame { ticks: 2, alpha: 1.0 });
+        assert_eq!(timer.advance(1000.0), Frame { ticks: 0, alpha: 1.0 });
+
+        timer.resume();
+        assert_eq!(timer.advance(1010.0).ticks, 0);
+        assert_eq!(timer.advance(1020.0).ticks, 1);
+        assert!(timer.set_tick_rate(0.0).is_err());
+    }
+}
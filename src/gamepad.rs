@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+use wasm_bindgen::JsCast;
+use web_sys::*;
+
+const DEFAULT_DEADZONE: f32 = 0.15;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    ResetCamera,
+    ToggleAxes,
+    ToggleContours,
+    ToggleScene,
+    ToggleWireframe,
+    ZoomIn,
+    ZoomOut,
+}
+
+impl Action {
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name {
+            "reset_camera" => Ok(Action::ResetCamera),
+            "toggle_axes" => Ok(Action::ToggleAxes),
+            "toggle_contours" => Ok(Action::ToggleContours),
+            "toggle_scene" => Ok(Action::ToggleScene),
+            "toggle_wireframe" => Ok(Action::ToggleWireframe),
+            "zoom_in" => Ok(Action::ZoomIn),
+            "zoom_out" => Ok(Action::ZoomOut),
+            _ => Err(format!("Unknown action: {}", name)),
+        }
+    }
+}
+
+/// Which axes and buttons of a controller drive what, using the indices of
+/// the browser's `standard` gamepad mapping by default.
+#[derive(Clone, Debug)]
+pub struct GamepadLayout {
+    pub move_axes: [usize; 2],
+    pub look_axes: [usize; 2],
+    pub deadzone: f32,
+    pub buttons: Vec<(usize, Action)>,
+}
+
+impl GamepadLayout {
+    pub fn standard() -> Self {
+        Self {
+            move_axes: [0, 1],
+            look_axes: [2, 3],
+            deadzone: DEFAULT_DEADZONE,
+            buttons: vec![
+                (0, Action::ToggleWireframe),
+                (1, Action::ToggleContours),
+                (2, Action::ToggleAxes),
+                (3, Action::ToggleScene),
+                (6, Action::ZoomOut),
+                (7, Action::ZoomIn),
+                (9, Action::ResetCamera),
+            ],
+        }
+    }
+
+    pub fn bind(&mut self, button: usize, action: Action) {
+        self.buttons.retain(|&(bound, _)| bound != button);
+        self.buttons.push((button, action));
+    }
+}
+
+pub struct GamepadSnapshot {
+    pub index: u32,
+    pub id: String,
+    pub axes: Vec<f32>,
+    pub buttons: Vec<bool>,
+}
+
+#[derive(Default)]
+pub struct GamepadFrame {
+    pub movement: (f32, f32),
+    pub look: (f32, f32),
+    pub held: Vec<Action>,
+    pub pressed: Vec<Action>,
+}
+
+/// Scales a stick radially so the deadzone reads as zero and the rest of
+/// the travel still reaches full deflection.
+pub fn apply_deadzone(x: f32, y: f32, deadzone: f32) -> (f32, f32) {
+    let magnitude = x.hypot(y);
+    if magnitude <= deadzone || magnitude == 0.0 {
+        return (0.0, 0.0);
+    }
+
+    let scaled = ((magnitude - deadzone) / (1.0 - deadzone)).min(1.0);
+    (x / magnitude * scaled, y / magnitude * scaled)
+}
+
+pub fn read_gamepads() -> Vec<GamepadSnapshot> {
+    let pads = match window().map(|window| window.navigator().get_gamepads()) {
+        Some(Ok(pads)) => pads,
+        _ => return Vec::new(),
+    };
+
+    pads.iter()
+        .filter_map(|pad| pad.dyn_into::<Gamepad>().ok())
+        .filter(|pad| pad.connected())
+        .map(|pad| GamepadSnapshot {
+            index: pad.index(),
+            id: pad.id(),
+            axes: pad.axes().iter().map(|axis| axis.as_f64().unwrap_or(0.0) as f32).collect(),
+            buttons: pad
+                .buttons()
+                .iter()
+                .map(|button| button.dyn_into::<GamepadButton>().map(|b| b.pressed()).unwrap_or(false))
+                .collect(),
+        })
+        .collect()
+}
+
+pub struct GamepadInput {
+    pub default_layout: GamepadLayout,
+    layouts: HashMap<String, GamepadLayout>,
+    previous_buttons: HashMap<u32, Vec<bool>>,
+}
+
+impl GamepadInput {
+    pub fn new() -> Self {
+        Self {
+            default_layout: GamepadLayout::standard(),
+            layouts: HashMap::new(),
+            previous_buttons: HashMap::new(),
+        }
+    }
+
+    /// The layout for a controller `id`, or the default layout for an empty
+    /// id; a controller's layout starts as a copy of the default.
+    pub fn get_layout_mut(&mut self, id: &str) -> &mut GamepadLayout {
+        if id.is_empty() {
+            return &mut self.default_layout;
+        }
+
+        let default_layout = &self.default_layout;
+        self.layouts.entry(id.to_string()).or_insert_with(|| default_layout.clone())
+    }
+
+    /// Merges every connected controller into one frame of input, reporting
+    /// buttons in `pressed` only on the frame they go down.
+    pub fn poll(&mut self, pads: &[GamepadSnapshot]) -> GamepadFrame {
+        let mut frame = GamepadFrame::default();
+
+        for pad in pads {
+            let layout = self.layouts.get(&pad.id).unwrap_or(&self.default_layout);
+            let axis = |index: usize| pad.axes.get(index).cloned().unwrap_or(0.0);
+
+            let movement = apply_deadzone(axis(layout.move_axes[0]), axis(layout.move_axes[1]), layout.deadzone);
+            let look = apply_deadzone(axis(layout.look_axes[0]), axis(layout.look_axes[1]), layout.deadzone);
+            frame.movement = (frame.movement.0 + movement.0, frame.movement.1 + movement.1);
+            frame.look = (frame.look.0 + look.0, frame.look.1 + look.1);
+
+            let previous = self.previous_buttons.get(&pad.index);
+            for &(button, action) in layout.buttons.iter() {
+                if !pad.buttons.get(button).cloned().unwrap_or(false) {
+                    continue;
+                }
+
+                frame.held.push(action);
+                let was_down = previous.and_then(|buttons| buttons.get(button)).cloned().unwrap_or(false);
+                if !was_down {
+                    frame.pressed.push(action);
+                }
+            }
+        }
+
+        self.previous_buttons = pads.iter().map(|pad| (pad.index, pad.buttons.clone())).collect();
+        frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pad(id: &str, axes: Vec<f32>, pressed: &[usize]) -> GamepadSnapshot {
+        let mut buttons = vec![false; 16];
+        for &button in pressed.iter() {
+            buttons[button] = true;
+        }
+        GamepadSnapshot { index: 0, id: id.to_string(), axes: axes, buttons: buttons }
+    }
+
+    fn assert_close(actual: (f32, f32), expected: (f32, f32)) {
+        assert!(
+            (actual.0 - expected.0).abs() < 1e-6 && (actual.1 - expected.1).abs() < 1e-6,
+            "{:?} != {:?}", actual, expected,
+        );
+    }
+
+    #[test]
+    fn deadzone_rescales_radially() {
+        assert_eq!(apply_deadzone(0.0, 0.0, 0.2), (0.0, 0.0));
+        assert_eq!(apply_deadzone(0.2, 0.0, 0.2), (0.0, 0.0));
+        assert_close(apply_deadzone(0.6, 0.0, 0.2), (0.5, 0.0));
+        assert_close(apply_deadzone(0.0, -1.0, 0.2), (0.0, -1.0));
+        assert_close(apply_deadzone(0.6, 0.8, 0.2), (0.6, 0.8));
+        assert_close(apply_deadzone(1.2, 1.6, 0.2), (0.6, 0.8));
+    }
+
+    #[test]
+    fn buttons_are_pressed_once_and_held_while_down() {
+        let mut input = GamepadInput::new();
+
+        let frame = input.poll(&[pad("pad", vec![], &[0])]);
+        assert_eq!(frame.pressed, vec![Action::ToggleWireframe]);
+        assert_eq!(frame.held, vec![Action::ToggleWireframe]);
+
+        let frame = input.poll(&[pad("pad", vec![], &[0, 1])]);
+        assert_eq!(frame.pressed, vec![Action::ToggleContours]);
+        assert_eq!(frame.held, vec![Action::ToggleWireframe, Action::ToggleContours]);
+
+        let frame = input.poll(&[pad("pad", vec![], &[])]);
+        assert!(frame.pressed.is_empty() && frame.held.is_empty());
+    }
+
+    #[test]
+    fn controllers_use_their_own_layout_or_the_default() {
+        let mut input = GamepadInput::new();
+        let layout = input.get_layout_mut("arcade stick");
+        layout.bind(0, Action::ResetCamera);
+        layout.move_axes = [2, 3];
+        layout.deadzone = 0.0;
+
+        let frame = input.poll(&[pad("arcade stick", vec![0.9, 0.0, 0.5, 0.0], &[0])]);
+        assert_eq!(frame.pressed, vec![Action::ResetCamera]);
+        assert_close(frame.movement, (0.5, 0.0));
+
+        let mut input = GamepadInput::new();
+        input.get_layout_mut("arcade stick").bind(0, Action::ResetCamera);
+        let frame = input.poll(&[pad("standard pad", vec![0.0; 4], &[0])]);
+        assert_eq!(frame.pressed, vec![Action::ToggleWireframe]);
+    }
+}
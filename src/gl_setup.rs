@@ -3,8 +3,20 @@ use wasm_bindgen::JsValue;
 use wasm_bindgen::prelude::*;
 use web_sys::*;
 use web_sys::WebGlRenderingContext as GL;
+use super::app_state::{Event, EventQueue};
 
-pub fn initialize_webgl_context() -> Result<GL, JsValue> {
+#[wasm_bindgen]
+extern "C" {
+    type ResizeObserver;
+
+    #[wasm_bindgen(constructor)]
+    fn new(callback: &js_sys::Function) -> ResizeObserver;
+
+    #[wasm_bindgen(method)]
+    fn observe(this: &ResizeObserver, target: &Element);
+}
+
+pub fn initialize_webgl_context(events: &EventQueue) -> Result<GL, JsValue> {
     let window = window().unwrap();
     let document = window.document().unwrap();
     let canvas = document.get_element_by_id("drawTarget").unwrap();
@@ -12,47 +24,144 @@ pub fn initialize_webgl_context() -> Result<GL, JsValue> {
     let canvas: web_sys::HtmlCanvasElement = canvas.dyn_into::<web_sys::HtmlCanvasElement>()?;
     let gl: WebGlRenderingContext = canvas.get_context("webgl")?.unwrap().dyn_into()?;
 
-    attach_mouse_down_handler(&canvas)?;
-    attach_mouse_up_handler(&canvas)?;
-    attach_mouse_move_handler(&canvas)?;
+    attach_pointer_down_handler(&canvas, events)?;
+    attach_pointer_up_handler(&canvas, events)?;
+    attach_pointer_move_handler(&canvas, events)?;
+    attach_mouse_wheel_handler(&canvas, events)?;
+    attach_context_menu_handler(&canvas)?;
+    attach_key_handlers(&canvas, events)?;
+    attach_blur_handler(&window, &canvas, events)?;
+    attach_resize_observer(&canvas, events)?;
 
-    gl.enable(GL::BLEND);
     gl.blend_func(GL::SRC_ALPHA, GL::ONE_MINUS_SRC_ALPHA);
+    gl.depth_func(GL::LEQUAL);
+    gl.polygon_offset(1.0, 1.0);
     gl.clear_color(0.0, 0.0, 0.0, 1.0);
     gl.clear_depth(1.0);
 
     Ok(gl)
 }
 
-fn attach_mouse_down_handler(canvas: &HtmlCanvasElement) -> Result<(), JsValue> {
-    let handler = move |event: web_sys::MouseEvent| {
-        super::app_state::update_mouse_down(event.client_x() as f32, event.client_y() as f32, true);
+fn attach_pointer_down_handler(canvas: &HtmlCanvasElement, events: &EventQueue) -> Result<(), JsValue> {
+    let events = events.clone();
+    let target = canvas.clone();
+    let handler = move |event: web_sys::PointerEvent| {
+        let _ = target.set_pointer_capture(event.pointer_id());
+        events.borrow_mut().push(Event::PointerDown {
+            id: event.pointer_id(),
+            x: event.client_x() as f32,
+            y: event.client_y() as f32,
+            button: event.button(),
+        });
     };
 
     let handler = Closure::wrap(Box::new(handler) as Box<dyn FnMut(_)>);
-    canvas.add_event_listener_with_callback("mousedown", handler.as_ref().unchecked_ref())?;
+    canvas.add_event_listener_with_callback("pointerdown", handler.as_ref().unchecked_ref())?;
     handler.forget();
     Ok(())
 }
 
-fn attach_mouse_up_handler(canvas: &HtmlCanvasElement) -> Result<(), JsValue> {
-    let handler = move |event: web_sys::MouseEvent| {
-        super::app_state::update_mouse_down(event.client_x() as f32, event.client_y() as f32, false);
+fn attach_pointer_up_handler(canvas: &HtmlCanvasElement, events: &EventQueue) -> Result<(), JsValue> {
+    let events = events.clone();
+    let handler = move |event: web_sys::PointerEvent| {
+        events.borrow_mut().push(Event::PointerUp { id: event.pointer_id() });
     };
 
     let handler = Closure::wrap(Box::new(handler) as Box<dyn FnMut(_)>);
-    canvas.add_event_listener_with_callback("mouseup", handler.as_ref().unchecked_ref())?;
+    canvas.add_event_listener_with_callback("pointerup", handler.as_ref().unchecked_ref())?;
+    canvas.add_event_listener_with_callback("pointercancel", handler.as_ref().unchecked_ref())?;
     handler.forget();
     Ok(())
 }
 
-fn attach_mouse_move_handler(canvas: &HtmlCanvasElement) -> Result<(), JsValue> {
+fn attach_pointer_move_handler(canvas: &HtmlCanvasElement, events: &EventQueue) -> Result<(), JsValue> {
+    let events = events.clone();
+    let handler = move |event: web_sys::PointerEvent| {
+        events.borrow_mut().push(Event::PointerMove {
+            id: event.pointer_id(),
+            x: event.client_x() as f32,
+            y: event.client_y() as f32,
+        });
+    };
+
+    let handler = Closure::wrap(Box::new(handler) as Box<dyn FnMut(_)>);
+    canvas.add_event_listener_with_callback("pointermove", handler.as_ref().unchecked_ref())?;
+    handler.forget();
+    Ok(())
+}
+
+fn attach_mouse_wheel_handler(canvas: &HtmlCanvasElement, events: &EventQueue) -> Result<(), JsValue> {
+    let events = events.clone();
+    let handler = move |event: web_sys::WheelEvent| {
+        event.prevent_default();
+        events.borrow_mut().push(Event::Wheel { delta_y: event.delta_y() as f32, is_pinch: event.ctrl_key() });
+    };
+
+    let handler = Closure::wrap(Box::new(handler) as Box<dyn FnMut(_)>);
+    canvas.add_event_listener_with_callback("wheel", handler.as_ref().unchecked_ref())?;
+    handler.forget();
+    Ok(())
+}
+
+fn attach_context_menu_handler(canvas: &HtmlCanvasElement) -> Result<(), JsValue> {
     let handler = move |event: web_sys::MouseEvent| {
-        super::app_state::update_mouse_position(event.client_x() as f32, event.client_y() as f32);
+        event.prevent_default();
     };
 
     let handler = Closure::wrap(Box::new(handler) as Box<dyn FnMut(_)>);
-    canvas.add_event_listener_with_callback("mousemove", handler.as_ref().unchecked_ref())?;
+    canvas.add_event_listener_with_callback("contextmenu", handler.as_ref().unchecked_ref())?;
+    handler.forget();
+    Ok(())
+}
+
+/// Listens on the canvas rather than the window so typing into the page's
+/// inputs never moves the camera; the tab index lets a click focus it.
+fn attach_key_handlers(canvas: &HtmlCanvasElement, events: &EventQueue) -> Result<(), JsValue> {
+    canvas.set_attribute("tabindex", "0")?;
+    for &(name, is_down) in [("keydown", true), ("keyup", false)].iter() {
+        let events = events.clone();
+        let handler = move |event: web_sys::KeyboardEvent| {
+            events.borrow_mut().push(Event::Key { key: event.key(), is_down: is_down });
+        };
+
+        let handler = Closure::wrap(Box::new(handler) as Box<dyn FnMut(_)>);
+        canvas.add_event_listener_with_callback(name, handler.as_ref().unchecked_ref())?;
+        handler.forget();
+    }
+    Ok(())
+}
+
+/// Key releases are never seen once focus moves away, e.g. on alt-tab, so
+/// losing focus lets go of every held key.
+fn attach_blur_handler(window: &Window, canvas: &HtmlCanvasElement, events: &EventQueue) -> Result<(), JsValue> {
+    let events = events.clone();
+    let handler = move || {
+        events.borrow_mut().push(Event::ReleaseKeys);
+    };
+
+    let handler = Closure::wrap(Box::new(handler) as Box<dyn FnMut()>);
+    window.add_event_listener_with_callback("blur", handler.as_ref().unchecked_ref())?;
+    canvas.add_event_listener_with_callback("blur", handler.as_ref().unchecked_ref())?;
     handler.forget();
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Reports the canvas's CSS size; the drawing buffer is sized to match at
+/// render time, when the device pixel ratio and render scale are known.
+fn attach_resize_observer(canvas: &HtmlCanvasElement, events: &EventQueue) -> Result<(), JsValue> {
+    let events = events.clone();
+    let target = canvas.clone();
+    let handler = move || {
+        events.borrow_mut().push(Event::Resize {
+            canvas_height: target.client_height() as f32,
+            canvas_width: target.client_width() as f32,
+        });
+    };
+
+    let handler = Closure::wrap(Box::new(handler) as Box<dyn FnMut()>);
+    let observer = ResizeObserver::new(handler.as_ref().unchecked_ref());
+    observer.observe(canvas);
+    handler.forget();
+    std::mem::forget(observer);
+    Ok(())
+}
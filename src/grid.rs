@@ -0,0 +1,107 @@
+use super::constants::*;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Shading {
+    Flat,
+    Smooth,
+}
+
+/// Whether `[min, max]` is a non-empty interval with a finite width.
+pub fn is_valid_range(min: f32, max: f32) -> bool {
+    min < max && (max - min).is_finite()
+}
+
+/// Resolution and model-space extents of a `columns x rows` quad grid, laid
+/// out row by row along z with `columns + 1` points per row.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GridLayout {
+    pub columns: usize,
+    pub rows: usize,
+    pub x_min: f32,
+    pub x_max: f32,
+    pub z_min: f32,
+    pub z_max: f32,
+}
+
+impl Default for GridLayout {
+    fn default() -> Self {
+        Self {
+            columns: DEFAULT_GRID_SIZE,
+            rows: DEFAULT_GRID_SIZE,
+            x_min: -1.0,
+            x_max: 1.0,
+            z_min: -1.0,
+            z_max: 1.0,
+        }
+    }
+}
+
+impl GridLayout {
+    pub fn with_resolution(self, columns: usize, rows: usize) -> Result<Self, String> {
+        let vertex_count = columns.checked_add(1).and_then(|c| rows.checked_add(1).and_then(|r| c.checked_mul(r)));
+        let is_too_big = match vertex_count {
+            Some(vertex_count) => vertex_count > MAX_GRID_VERTICES,
+            None => true,
+        };
+        if columns == 0 || rows == 0 || is_too_big {
+            return Err(format!(
+                "Grid resolution {}x{} must be at least 1x1 with at most {} vertices",
+                columns, rows, MAX_GRID_VERTICES,
+            ));
+        }
+
+        Ok(Self { columns: columns, rows: rows, ..self })
+    }
+
+    pub fn with_extents(self, x_min: f32, x_max: f32, z_min: f32, z_max: f32) -> Result<Self, String> {
+        if !is_valid_range(x_min, x_max) || !is_valid_range(z_min, z_max) {
+            return Err(format!(
+                "Invalid grid extents x: [{}, {}], z: [{}, {}]",
+                x_min, x_max, z_min, z_max,
+            ));
+        }
+
+        Ok(Self { x_min: x_min, x_max: x_max, z_min: z_min, z_max: z_max, ..self })
+    }
+
+    pub fn points_per_row(&self) -> usize {
+        self.columns + 1
+    }
+
+    pub fn point_count(&self) -> usize {
+        (self.columns + 1) * (self.rows + 1)
+    }
+
+    pub fn cell_width(&self) -> f32 {
+        (self.x_max - self.x_min) / self.columns as f32
+    }
+
+    pub fn cell_depth(&self) -> f32 {
+        (self.z_max - self.z_min) / self.rows as f32
+    }
+
+    pub fn position(&self, x: usize, z: usize) -> (f32, f32) {
+        (
+            self.x_min + x as f32 * self.cell_width(),
+            self.z_min + z as f32 * self.cell_depth(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolution_rejects_empty_and_oversized_grids() {
+        let layout = GridLayout::default();
+
+        assert!(layout.with_resolution(0, 4).is_err());
+        assert!(layout.with_resolution(4, 0).is_err());
+        assert!(layout.with_resolution(usize::MAX, 1).is_err());
+        assert!(layout.with_resolution(1, usize::MAX).is_err());
+        assert!(layout.with_resolution(usize::MAX / 2, usize::MAX / 2).is_err());
+        assert!(layout.with_resolution(65535, 65535).is_err());
+        assert_eq!(layout.with_resolution(3, 2).unwrap().point_count(), 12);
+    }
+}
@@ -0,0 +1,225 @@
+pub struct Heightmap {
+    pub width: usize,
+    pub height: usize,
+    pub values: Vec<f32>,
+}
+
+impl Heightmap {
+    pub fn from_png(bytes: &[u8]) -> Result<Self, String> {
+        let mut decoder = png::Decoder::new(bytes);
+        decoder.set_transformations(png::Transformations::EXPAND);
+        let (info, mut reader) = decoder
+            .read_info()
+            .map_err(|err| format!("Unable to read PNG: {}", err))?;
+
+        let mut buf = vec![0; info.buffer_size()];
+        reader
+            .next_frame(&mut buf)
+            .map_err(|err| format!("Unable to decode PNG: {}", err))?;
+
+        let samples = info.color_type.samples();
+        let sample_bytes = match info.bit_depth {
+            png::BitDepth::Sixteen => 2,
+            _ => 1,
+        };
+        let max_sample = if sample_bytes == 2 { 65535.0 } else { 255.0 };
+
+        let width = info.width as usize;
+        let height = info.height as usize;
+        let mut values = Vec::with_capacity(width * height);
+
+        for row in buf.chunks(info.line_size).take(height) {
+            for pixel in row.chunks(samples * sample_bytes).take(width) {
+                let sample = |i: usize| -> f32 {
+                    let start = i * sample_bytes;
+                    let raw = if sample_bytes == 2 {
+                        (pixel[start] as u32) << 8 | pixel[start + 1] as u32
+                    } else {
+                        pixel[start] as u32
+                    };
+                    raw as f32 / max_sample
+                };
+
+                values.push(match info.color_type {
+                    png::ColorType::RGB | png::ColorType::RGBA => {
+                        0.299 * sample(0) + 0.587 * sample(1) + 0.114 * sample(2)
+                    },
+                    _ => sample(0),
+                });
+            }
+        }
+
+        Self::new(width, height, values)
+    }
+
+    pub fn from_csv(text: &str) -> Result<Self, String> {
+        let mut width = 0;
+        let mut values = Vec::new();
+        let mut height = 0;
+
+        for (line_index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut row_width = 0;
+            for cell in line.split([',', ';', '\t']) {
+                let cell = cell.trim();
+                let value = cell.parse::<f32>().map_err(|_| {
+                    format!("Invalid number '{}' on line {}", cell, line_index + 1)
+                })?;
+                values.push(value);
+                row_width += 1;
+            }
+
+            if height == 0 {
+                width = row_width;
+            } else if row_width != width {
+                return Err(format!(
+                    "Line {} has {} values but the first row has {}",
+                    line_index + 1,
+                    row_width,
+                    width,
+                ));
+            }
+            height += 1;
+        }
+
+        Self::new(width, height, values)
+    }
+
+    fn new(width: usize, height: usize, values: Vec<f32>) -> Result<Self, String> {
+        if width < 2 || height < 2 {
+            return Err(format!("Heightmap must be at least 2x2, got {}x{}", width, height));
+        }
+
+        if let Some(value) = values.iter().find(|value| !value.is_finite()) {
+            return Err(format!("Heightmap contains non-finite value {}", value));
+        }
+
+        Ok(Self {
+            width: width,
+            height: height,
+            values: values,
+        })
+    }
+
+    pub fn get_range(&self) -> (f32, f32) {
+        self.values.iter().fold((f32::MAX, f32::MIN), |(min, max), &value| {
+            (min.min(value), max.max(value))
+        })
+    }
+
+    /// Bilinearly samples the map onto the `(columns + 1) x (rows + 1)` vertex
+    /// layout of `get_position_grid`, with image rows running along z.
+    pub fn resample(&self, columns: usize, rows: usize) -> Vec<f32> {
+        let points_per_row = columns + 1;
+        let mut y_vals = vec![0.0; points_per_row * (rows + 1)];
+
+        for z in 0..rows + 1 {
+            let source_z = z as f32 * (self.height - 1) as f32 / rows as f32;
+            let z0 = (source_z.floor() as usize).min(self.height - 2);
+            let z_frac = source_z - z0 as f32;
+
+            for x in 0..points_per_row {
+                let source_x = x as f32 * (self.width - 1) as f32 / columns as f32;
+                let x0 = (source_x.floor() as usize).min(self.width - 2);
+                let x_frac = source_x - x0 as f32;
+
+                let at = |x: usize, z: usize| self.values[z * self.width + x];
+                let near = at(x0, z0) + x_frac * (at(x0 + 1, z0) - at(x0, z0));
+                let far = at(x0, z0 + 1) + x_frac * (at(x0 + 1, z0 + 1) - at(x0, z0 + 1));
+
+                y_vals[z * points_per_row + x] = near + z_frac * (far - near);
+            }
+        }
+
+        y_vals
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode_png(width: u32, height: u32, color: png::ColorType, depth: png::BitDepth, data: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut bytes, width, height);
+            encoder.set_color(color);
+            encoder.set_depth(depth);
+            let mut writer = encoder.write_header().unwrap();
+            writer.write_image_data(data).unwrap();
+        }
+        bytes
+    }
+
+    #[test]
+    fn reads_greyscale_png() {
+        let bytes = encode_png(2, 2, png::ColorType::Grayscale, png::BitDepth::Eight, &[0, 255, 51, 102]);
+        let map = Heightmap::from_png(&bytes).unwrap();
+
+        assert_eq!((map.width, map.height), (2, 2));
+        assert_eq!(map.values, vec![0.0, 1.0, 0.2, 0.4]);
+    }
+
+    #[test]
+    fn reads_sixteen_bit_and_rgb_png() {
+        let bytes = encode_png(2, 2, png::ColorType::Grayscale, png::BitDepth::Sixteen, &[0, 0, 255, 255, 128, 0, 0, 0]);
+        let map = Heightmap::from_png(&bytes).unwrap();
+        assert_eq!(map.values[1], 1.0);
+        assert!((map.values[2] - 32768.0 / 65535.0).abs() < 1e-6);
+
+        let white_black = [255, 255, 255, 0, 0, 0, 0, 0, 0, 255, 255, 255];
+        let bytes = encode_png(2, 2, png::ColorType::RGB, png::BitDepth::Eight, &white_black);
+        let map = Heightmap::from_png(&bytes).unwrap();
+        assert!((map.values[0] - 1.0).abs() < 1e-6);
+        assert_eq!(map.values[1], 0.0);
+    }
+
+    #[test]
+    fn rejects_invalid_png() {
+        assert!(Heightmap::from_png(b"not a png").is_err());
+    }
+
+    #[test]
+    fn reads_csv_matrix() {
+        let map = Heightmap::from_csv("# depth\n1, 2, 3\n4;5;6\n\n7\t8\t9\n").unwrap();
+
+        assert_eq!((map.width, map.height), (3, 3));
+        assert_eq!(map.values, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0]);
+        assert_eq!(map.get_range(), (1.0, 9.0));
+    }
+
+    #[test]
+    fn rejects_ragged_or_invalid_csv() {
+        assert!(Heightmap::from_csv("1,2\n3\n").is_err());
+        assert!(Heightmap::from_csv("1,x\n3,4\n").is_err());
+        assert!(Heightmap::from_csv("1,2\n").is_err());
+    }
+
+    #[test]
+    fn resamples_bilinearly() {
+        let map = Heightmap::from_csv("0,2\n4,6\n").unwrap();
+
+        assert_eq!(map.resample(1, 1), vec![0.0, 2.0, 4.0, 6.0]);
+        assert_eq!(map.resample(2, 2), vec![0.0, 1.0, 2.0, 2.0, 3.0, 4.0, 4.0, 5.0, 6.0]);
+    }
+
+    #[test]
+    fn resamples_onto_non_square_grid() {
+        let map = Heightmap::from_csv("0,2\n4,6\n").unwrap();
+
+        assert_eq!(map.resample(2, 1), vec![0.0, 1.0, 2.0, 4.0, 5.0, 6.0]);
+        assert_eq!(map.resample(1, 2), vec![0.0, 2.0, 2.0, 4.0, 4.0, 6.0]);
+    }
+
+    #[test]
+    fn resamples_larger_map_down_to_grid_corners() {
+        let map = Heightmap::from_csv("0,1,2,3\n4,5,6,7\n8,9,10,11\n12,13,14,15\n").unwrap();
+        let y_vals = map.resample(1, 1);
+
+        assert_eq!(y_vals, vec![0.0, 3.0, 12.0, 15.0]);
+    }
+}
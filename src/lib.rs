@@ -1,15 +1,32 @@
 extern crate wasm_bindgen;
+use std::cell::RefCell;
+use std::rc::Rc;
 use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
 use web_sys::WebGlRenderingContext as GL;
 
-#[macro_use]
-extern crate lazy_static;
-
 mod app_state;
+mod buffer_pool;
+mod camera;
+mod color_map;
 mod common_funcs;
 mod constants;
+mod contours;
+mod expression;
+mod font;
+mod frame_stats;
+mod function_plot;
+mod game_loop;
+mod gamepad;
 mod gl_setup;
+mod glyph_atlas;
+mod grid;
+mod heightmap;
+mod quad_batch;
+mod recording;
+mod render_state;
 mod shaders;
+mod ui;
 mod programs;
 
 
@@ -19,44 +36,1044 @@ extern "C" {
     pub fn log(s: &str);
 }
 
+const HOTBAR_ID: u32 = 100;
+const HOTBAR_SLOTS: usize = 9;
+const UI_MARGIN: f32 = 10.0;
+const CHAT_HISTORY: usize = 8;
+const CHAT_HEIGHT: f32 = 160.0;
+const CHAT_SCALE: f32 = 1.5;
+const CHAT_WIDTH: f32 = 360.0;
+const MIN_RENDER_SCALE: f32 = 0.25;
+const MAX_RENDER_SCALE: f32 = 2.0;
+
 #[wasm_bindgen]
 pub struct GameClient {
-    gl: GL,
-    program_cube_3d: programs::Cube3D,
+    client: Rc<RefCell<Client>>,
+    timer: Rc<RefCell<game_loop::FixedStep>>,
+    running: bool,
 }
 
 #[wasm_bindgen]
 impl GameClient {
     #[wasm_bindgen(constructor)]
     pub fn new() -> Self {
+        Self {
+            client: Rc::new(RefCell::new(Client::new())),
+            timer: Rc::new(RefCell::new(game_loop::FixedStep::new())),
+            running: false,
+        }
+    }
+
+    /// Starts the `requestAnimationFrame` loop: fixed-rate simulation ticks,
+    /// with every frame rendered between the last two ticks.
+    pub fn start(&mut self) -> Result<(), JsValue> {
+        if self.running {
+            return Ok(());
+        }
+
+        let client = self.client.clone();
+        let timer = self.timer.clone();
+        let callback: game_loop::FrameCallback = Rc::new(RefCell::new(None));
+        let next_frame = callback.clone();
+
+        *callback.borrow_mut() = Some(Closure::wrap(Box::new(move |frame_time: f64| {
+            client.borrow_mut().run_frame(&mut timer.borrow_mut(), frame_time);
+
+            if let Some(callback) = next_frame.borrow().as_ref() {
+                let _ = game_loop::request_animation_frame(callback);
+            }
+        }) as Box<dyn FnMut(f64)>));
+
+        game_loop::request_animation_frame(callback.borrow().as_ref().unwrap())?;
+        self.running = true;
+        Ok(())
+    }
+
+    pub fn pause(&mut self) {
+        self.timer.borrow_mut().pause();
+    }
+
+    pub fn resume(&mut self) {
+        self.timer.borrow_mut().resume();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.timer.borrow().is_paused()
+    }
+
+    /// Runs a single tick on the next frame while paused.
+    pub fn step(&mut self) {
+        self.timer.borrow_mut().step();
+    }
+
+    pub fn set_tick_rate(&mut self, ticks_per_second: f32) -> Result<(), JsValue> {
+        self.timer.borrow_mut().set_tick_rate(ticks_per_second).map_err(|err| JsValue::from_str(&err))
+    }
+
+    pub fn get_tick_rate(&self) -> f32 {
+        self.timer.borrow().get_tick_rate()
+    }
+
+    pub fn gpu_buffer_count(&self) -> usize {
+        self.client.borrow().gpu_buffer_count()
+    }
+
+    pub fn gpu_buffer_bytes(&self) -> usize {
+        self.client.borrow().gpu_buffer_bytes()
+    }
+
+    pub fn bytes_uploaded_last_frame(&self) -> usize {
+        self.client.borrow().bytes_uploaded_last_frame()
+    }
+
+    pub fn get_frame_stats(&self) -> frame_stats::FrameStats {
+        self.client.borrow().get_frame_stats()
+    }
+
+    pub fn set_perf_hud(&mut self, enabled: bool) {
+        self.client.borrow_mut().set_perf_hud(enabled)
+    }
+
+    pub fn set_ui(&mut self, enabled: bool) {
+        self.client.borrow_mut().set_ui(enabled)
+    }
+
+    pub fn set_ui_opacity(&mut self, opacity: f32) {
+        self.client.borrow_mut().set_ui_opacity(opacity)
+    }
+
+    pub fn set_health(&mut self, fraction: f32) {
+        self.client.borrow_mut().set_health(fraction)
+    }
+
+    pub fn set_hotbar_slot(&mut self, slot: usize) -> Result<(), JsValue> {
+        self.client.borrow_mut().set_hotbar_slot(slot)
+    }
+
+    pub fn get_hotbar_slot(&self) -> usize {
+        self.client.borrow().get_hotbar_slot()
+    }
+
+    pub fn push_chat_message(&mut self, text: &str) {
+        self.client.borrow_mut().push_chat_message(text)
+    }
+
+    pub fn load_font(&mut self, descriptor: &str, atlas_png: &[u8], distance_range: Option<f32>) -> Result<(), JsValue> {
+        self.client.borrow_mut().load_font(descriptor, atlas_png, distance_range)
+    }
+
+    pub fn set_scene(&mut self, scene: &str) -> Result<(), JsValue> {
+        self.client.borrow_mut().set_scene(scene)
+    }
+
+    pub fn set_camera_mode(&mut self, mode: &str) -> Result<(), JsValue> {
+        self.client.borrow_mut().set_camera_mode(mode)
+    }
+
+    pub fn set_camera_target(&mut self, x: f32, y: f32, z: f32) {
+        self.client.borrow_mut().set_camera_target(x, y, z)
+    }
+
+    pub fn set_camera_damping(&mut self, damping: f32) -> Result<(), JsValue> {
+        self.client.borrow_mut().set_camera_damping(damping)
+    }
+
+    pub fn reset_camera(&mut self) {
+        self.client.borrow_mut().reset_camera()
+    }
+
+    /// Drawing buffer pixels per device pixel, e.g. 0.5 to render at half
+    /// resolution on a high-DPI screen.
+    pub fn set_render_scale(&mut self, scale: f32) -> Result<(), JsValue> {
+        self.client.borrow_mut().set_render_scale(scale)
+    }
+
+    pub fn get_render_scale(&self) -> f32 {
+        self.client.borrow().get_render_scale()
+    }
+
+    pub fn set_gpu_heightfield(&mut self, enabled: bool) {
+        self.client.borrow_mut().set_gpu_heightfield(enabled)
+    }
+
+    pub fn set_function(&mut self, source: &str) -> Result<(), JsValue> {
+        self.client.borrow_mut().set_function(source)
+    }
+
+    pub fn clear_function(&mut self) {
+        self.client.borrow_mut().clear_function()
+    }
+
+    pub fn load_heightmap_png(&mut self, bytes: &[u8]) -> Result<(), JsValue> {
+        self.client.borrow_mut().load_heightmap_png(bytes)
+    }
+
+    pub fn load_heightmap_csv(&mut self, text: &str) -> Result<(), JsValue> {
+        self.client.borrow_mut().load_heightmap_csv(text)
+    }
+
+    pub fn set_plot_domain(&mut self, x_min: f32, x_max: f32, z_min: f32, z_max: f32) -> Result<(), JsValue> {
+        self.client.borrow_mut().set_plot_domain(x_min, x_max, z_min, z_max)
+    }
+
+    pub fn set_plot_resolution(&mut self, resolution: usize) -> Result<(), JsValue> {
+        self.client.borrow_mut().set_plot_resolution(resolution)
+    }
+
+    pub fn set_grid_resolution(&mut self, columns: usize, rows: usize) -> Result<(), JsValue> {
+        self.client.borrow_mut().set_grid_resolution(columns, rows)
+    }
+
+    pub fn set_grid_extents(&mut self, x_min: f32, x_max: f32, z_min: f32, z_max: f32) -> Result<(), JsValue> {
+        self.client.borrow_mut().set_grid_extents(x_min, x_max, z_min, z_max)
+    }
+
+    pub fn set_plot_scale(&mut self, y_scale: f32) {
+        self.client.borrow_mut().set_plot_scale(y_scale)
+    }
+
+    pub fn set_color_map(&mut self, name: &str) -> Result<(), JsValue> {
+        self.client.borrow_mut().set_color_map(name)
+    }
+
+    pub fn set_custom_color_map(&mut self, stops: &[f32]) -> Result<(), JsValue> {
+        self.client.borrow_mut().set_custom_color_map(stops)
+    }
+
+    pub fn get_color_map_ramp(&self, steps: usize) -> Vec<f32> {
+        self.client.borrow().get_color_map_ramp(steps)
+    }
+
+    pub fn set_color_range(&mut self, min: f32, max: f32) -> Result<(), JsValue> {
+        self.client.borrow_mut().set_color_range(min, max)
+    }
+
+    pub fn set_auto_color_range(&mut self) {
+        self.client.borrow_mut().set_auto_color_range()
+    }
+
+    pub fn get_color_range(&self) -> Vec<f32> {
+        self.client.borrow().get_color_range()
+    }
+
+    pub fn set_shading(&mut self, shading: &str) -> Result<(), JsValue> {
+        self.client.borrow_mut().set_shading(shading)
+    }
+
+    pub fn set_wireframe(&mut self, enabled: bool) {
+        self.client.borrow_mut().set_wireframe(enabled)
+    }
+
+    pub fn set_contours(&mut self, enabled: bool) {
+        self.client.borrow_mut().set_contours(enabled)
+    }
+
+    pub fn set_contour_interval(&mut self, interval: f32) -> Result<(), JsValue> {
+        self.client.borrow_mut().set_contour_interval(interval)
+    }
+
+    pub fn set_axes(&mut self, enabled: bool) {
+        self.client.borrow_mut().set_axes(enabled)
+    }
+
+    pub fn set_axis_divisions(&mut self, divisions: usize) -> Result<(), JsValue> {
+        self.client.borrow_mut().set_axis_divisions(divisions)
+    }
+
+    pub fn set_gamepad_binding(&mut self, controller_id: &str, button: usize, action: &str) -> Result<(), JsValue> {
+        self.client.borrow_mut().set_gamepad_binding(controller_id, button, action)
+    }
+
+    pub fn set_gamepad_axes(
+        &mut self,
+        controller_id: &str,
+        move_x: usize,
+        move_y: usize,
+        look_x: usize,
+        look_y: usize,
+    ) {
+        self.client.borrow_mut().set_gamepad_axes(controller_id, move_x, move_y, look_x, look_y)
+    }
+
+    pub fn set_gamepad_deadzone(&mut self, controller_id: &str, deadzone: f32) -> Result<(), JsValue> {
+        self.client.borrow_mut().set_gamepad_deadzone(controller_id, deadzone)
+    }
+
+    pub fn start_recording(&mut self) {
+        self.client.borrow_mut().start_recording()
+    }
+
+    pub fn stop_recording(&mut self) -> Result<Vec<u8>, JsValue> {
+        self.client.borrow_mut().stop_recording()
+    }
+
+    /// Restores the state the log was recorded from and feeds it one recorded
+    /// frame per tick in place of live input. Logs made while the function,
+    /// heightmap, plot or grid changed are refused, as those are not recorded.
+    pub fn start_replay(&mut self, log: &[u8]) -> Result<(), JsValue> {
+        self.client.borrow_mut().start_replay(log)
+    }
+
+    pub fn stop_replay(&mut self) {
+        self.client.borrow_mut().stop_replay()
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.client.borrow().is_replaying()
+    }
+}
+
+struct Client {
+    gl: GL,
+    buffers: buffer_pool::BufferPool,
+    chat: Vec<String>,
+    events: app_state::EventQueue,
+    frame_profiler: frame_stats::FrameProfiler,
+    function_plot: function_plot::FunctionPlot,
+    gamepad: gamepad::GamepadInput,
+    gpu_heightfield: bool,
+    grid: grid::GridLayout,
+    health: f32,
+    hotbar_slot: usize,
+    perf_hud: programs::PerfHud,
+    previous_state: app_state::AppState,
+    program_axes_3d: programs::Axes3D,
+    program_cube_3d: programs::Cube3D,
+    program_graph_3d: programs::Graph3D,
+    program_quad_batch_2d: programs::QuadBatch2D,
+    program_text_2d: programs::Text2D,
+    program_ui_2d: programs::Ui2D,
+    recording: Option<recording::Recording>,
+    render_scale: f32,
+    render_states: render_state::RenderStateTracker,
+    replay: Option<recording::Replay>,
+    show_perf_hud: bool,
+    show_ui: bool,
+    state: app_state::AppState,
+    ui: ui::Ui,
+    ui_input: ui::UiInput,
+    ui_pointer: Option<i32>,
+}
+
+impl Client {
+    fn new() -> Self {
         console_error_panic_hook::set_once();
-        let gl = gl_setup::initialize_webgl_context().unwrap();
+        let events: app_state::EventQueue = Default::default();
+        let gl = gl_setup::initialize_webgl_context(&events).unwrap();
+        let buffers = buffer_pool::BufferPool::new(&gl);
+        let program_text_2d = programs::Text2D::new(&gl);
+        let ui = ui::Ui::new(program_text_2d.font().clone());
         Self {
-            program_cube_3d: programs::Cube3D::new(&gl),
+            program_axes_3d: programs::Axes3D::new(&gl, &buffers),
+            program_cube_3d: programs::Cube3D::new(&gl, &buffers),
+            program_graph_3d: programs::Graph3D::new(&gl, &buffers),
+            program_quad_batch_2d: programs::QuadBatch2D::new(&gl, &buffers),
+            program_text_2d: program_text_2d,
+            program_ui_2d: programs::Ui2D::new(),
+            buffers: buffers,
+            chat: Vec::new(),
+            events: events,
+            frame_profiler: frame_stats::FrameProfiler::new(),
+            function_plot: function_plot::FunctionPlot::new(),
+            gamepad: gamepad::GamepadInput::new(),
+            gpu_heightfield: true,
+            grid: grid::GridLayout::default(),
+            health: 1.0,
+            hotbar_slot: 0,
+            perf_hud: programs::PerfHud::new(),
+            previous_state: app_state::AppState::new(),
+            recording: None,
+            render_scale: 1.0,
+            render_states: render_state::RenderStateTracker::new(),
+            replay: None,
+            show_perf_hud: false,
+            show_ui: false,
+            state: app_state::AppState::new(),
+            ui: ui,
+            ui_input: ui::UiInput::default(),
+            ui_pointer: None,
             gl: gl,
         }
     }
 
-    pub fn update(&mut self, time: f32, height: f32, width: f32) -> Result<(), JsValue> {
-        app_state::update_dynamic_data(time, height, width);
+    fn gpu_buffer_count(&self) -> usize {
+        self.buffers.stats().buffer_count
+    }
+
+    fn gpu_buffer_bytes(&self) -> usize {
+        self.buffers.stats().bytes_allocated
+    }
+
+    fn bytes_uploaded_last_frame(&self) -> usize {
+        self.buffers.stats().last_frame_bytes_uploaded
+    }
+
+    fn get_frame_stats(&self) -> frame_stats::FrameStats {
+        self.frame_profiler.last
+    }
+
+    fn set_perf_hud(&mut self, enabled: bool) {
+        self.show_perf_hud = enabled;
+    }
+
+    fn set_ui(&mut self, enabled: bool) {
+        self.show_ui = enabled;
+    }
+
+    fn set_ui_opacity(&mut self, opacity: f32) {
+        self.program_ui_2d.opacity = opacity.clamp(0.0, 1.0);
+    }
+
+    fn set_health(&mut self, fraction: f32) {
+        self.health = fraction.clamp(0.0, 1.0);
+    }
+
+    fn set_hotbar_slot(&mut self, slot: usize) -> Result<(), JsValue> {
+        if slot >= HOTBAR_SLOTS {
+            return Err(JsValue::from_str(&format!("Hotbar slot must be below {}, got {}", HOTBAR_SLOTS, slot)));
+        }
+        self.hotbar_slot = slot;
+        Ok(())
+    }
+
+    fn get_hotbar_slot(&self) -> usize {
+        self.hotbar_slot
+    }
+
+    fn push_chat_message(&mut self, text: &str) {
+        if self.chat.len() == CHAT_HISTORY {
+            self.chat.remove(0);
+        }
+        self.chat.push(text.to_string());
+    }
+
+    fn load_font(&mut self, descriptor: &str, atlas_png: &[u8], distance_range: Option<f32>) -> Result<(), JsValue> {
+        let font = Rc::new(
+            font::Font::from_bmfont(descriptor, atlas_png, distance_range).map_err(|err| JsValue::from_str(&err))?,
+        );
+        self.program_text_2d.set_font(&self.gl, font.clone());
+        self.ui.set_font(font);
+        self.note_untracked_change("the font");
+        Ok(())
+    }
+
+    fn set_scene(&mut self, scene: &str) -> Result<(), JsValue> {
+        let scene = match scene {
+            "cube" => app_state::Scene::Cube,
+            "graph" => app_state::Scene::Graph,
+            _ => return Err(JsValue::from_str(&format!("Unknown scene: {}", scene))),
+        };
+        self.dispatch(app_state::Event::SetScene(scene));
+        Ok(())
+    }
+
+    fn set_camera_mode(&mut self, mode: &str) -> Result<(), JsValue> {
+        let camera_mode = match mode {
+            "turntable" => camera::CameraMode::Turntable,
+            "orbit" => camera::CameraMode::Orbit,
+            _ => return Err(JsValue::from_str(&format!("Unknown camera mode: {}", mode))),
+        };
+        self.dispatch(app_state::Event::SetCameraMode(camera_mode));
+        Ok(())
+    }
+
+    fn set_camera_target(&mut self, x: f32, y: f32, z: f32) {
+        self.dispatch(app_state::Event::SetCameraTarget([x, y, z]));
+    }
+
+    fn set_camera_damping(&mut self, damping: f32) -> Result<(), JsValue> {
+        if !(0.0..1.0).contains(&damping) {
+            return Err(JsValue::from_str(&format!("Camera damping must be in [0, 1), got {}", damping)));
+        }
+        self.dispatch(app_state::Event::SetCameraDamping(damping));
         Ok(())
     }
 
-    pub fn render(&self) {
+    fn reset_camera(&mut self) {
+        self.dispatch(app_state::Event::ResetCamera);
+    }
+
+    fn set_render_scale(&mut self, scale: f32) -> Result<(), JsValue> {
+        if !(MIN_RENDER_SCALE..=MAX_RENDER_SCALE).contains(&scale) {
+            return Err(JsValue::from_str(&format!(
+                "Render scale must be between {} and {}, got {}",
+                MIN_RENDER_SCALE, MAX_RENDER_SCALE, scale
+            )));
+        }
+        self.render_scale = scale;
+        self.note_untracked_change("the render scale");
+        Ok(())
+    }
+
+    fn get_render_scale(&self) -> f32 {
+        self.render_scale
+    }
+
+    fn set_gpu_heightfield(&mut self, enabled: bool) {
+        self.gpu_heightfield = enabled;
+        self.note_untracked_change("the heightfield mode");
+    }
+
+    fn set_function(&mut self, source: &str) -> Result<(), JsValue> {
+        self.function_plot.set_expression(source).map_err(|err| JsValue::from_str(&err))?;
+        self.note_untracked_change("the function");
+        Ok(())
+    }
+
+    fn clear_function(&mut self) {
+        self.function_plot.clear();
+        self.note_untracked_change("the function");
+    }
+
+    fn load_heightmap_png(&mut self, bytes: &[u8]) -> Result<(), JsValue> {
+        let heightmap = heightmap::Heightmap::from_png(bytes).map_err(|err| JsValue::from_str(&err))?;
+        self.function_plot.set_heightmap(heightmap);
+        self.note_untracked_change("the heightmap");
+        Ok(())
+    }
+
+    fn load_heightmap_csv(&mut self, text: &str) -> Result<(), JsValue> {
+        let heightmap = heightmap::Heightmap::from_csv(text).map_err(|err| JsValue::from_str(&err))?;
+        self.function_plot.set_heightmap(heightmap);
+        self.note_untracked_change("the heightmap");
+        Ok(())
+    }
+
+    fn set_plot_domain(&mut self, x_min: f32, x_max: f32, z_min: f32, z_max: f32) -> Result<(), JsValue> {
+        self.function_plot.set_domain(x_min, x_max, z_min, z_max).map_err(|err| JsValue::from_str(&err))?;
+        self.note_untracked_change("the plot domain");
+        Ok(())
+    }
+
+    fn set_plot_resolution(&mut self, resolution: usize) -> Result<(), JsValue> {
+        self.set_grid_resolution(resolution, resolution)
+    }
+
+    fn set_grid_resolution(&mut self, columns: usize, rows: usize) -> Result<(), JsValue> {
+        self.grid = self.grid.with_resolution(columns, rows).map_err(|err| JsValue::from_str(&err))?;
+        self.note_untracked_change("the grid");
+        Ok(())
+    }
+
+    fn set_grid_extents(&mut self, x_min: f32, x_max: f32, z_min: f32, z_max: f32) -> Result<(), JsValue> {
+        self.grid = self.grid.with_extents(x_min, x_max, z_min, z_max).map_err(|err| JsValue::from_str(&err))?;
+        self.note_untracked_change("the grid");
+        Ok(())
+    }
+
+    fn set_plot_scale(&mut self, y_scale: f32) {
+        self.function_plot.set_y_scale(y_scale);
+        self.note_untracked_change("the plot scale");
+    }
+
+    fn set_color_map(&mut self, name: &str) -> Result<(), JsValue> {
+        let color_map = color_map::ColorMap::from_name(name).map_err(|err| JsValue::from_str(&err))?;
+        self.program_graph_3d.set_color_map(&self.gl, color_map);
+        self.note_untracked_change("the colour map");
+        Ok(())
+    }
+
+    fn set_custom_color_map(&mut self, stops: &[f32]) -> Result<(), JsValue> {
+        let color_map = color_map::ColorMap::from_stops(stops).map_err(|err| JsValue::from_str(&err))?;
+        self.program_graph_3d.set_color_map(&self.gl, color_map);
+        self.note_untracked_change("the colour map");
+        Ok(())
+    }
+
+    fn get_color_map_ramp(&self, steps: usize) -> Vec<f32> {
+        self.program_graph_3d.color_map.get_ramp(steps)
+    }
+
+    fn set_color_range(&mut self, min: f32, max: f32) -> Result<(), JsValue> {
+        if !(min.is_finite() && max.is_finite() && min < max) {
+            return Err(JsValue::from_str(&format!("Colour range must be finite with min below max, got {}..{}", min, max)));
+        }
+        self.program_graph_3d.color_range = Some((min, max));
+        self.note_untracked_change("the colour range");
+        Ok(())
+    }
+
+    fn set_auto_color_range(&mut self) {
+        self.program_graph_3d.color_range = None;
+        self.note_untracked_change("the colour range");
+    }
+
+    fn get_color_range(&self) -> Vec<f32> {
+        let (min, max) = self.program_graph_3d.last_color_range.get();
+        vec![min, max]
+    }
+
+    fn set_shading(&mut self, shading: &str) -> Result<(), JsValue> {
+        self.program_graph_3d.shading = match shading {
+            "flat" => grid::Shading::Flat,
+            "smooth" => grid::Shading::Smooth,
+            _ => return Err(JsValue::from_str(&format!("Unknown shading mode: {}", shading))),
+        };
+        self.note_untracked_change("the shading");
+        Ok(())
+    }
+
+    fn set_wireframe(&mut self, enabled: bool) {
+        self.dispatch(app_state::Event::SetWireframe(enabled));
+    }
+
+    fn set_contours(&mut self, enabled: bool) {
+        self.dispatch(app_state::Event::SetContours(enabled));
+    }
+
+    fn set_contour_interval(&mut self, interval: f32) -> Result<(), JsValue> {
+        if !(interval > 0.0 && interval.is_finite()) {
+            return Err(JsValue::from_str(&format!("Contour interval must be positive, got {}", interval)));
+        }
+        self.program_graph_3d.contour_interval = interval;
+        self.note_untracked_change("the contour interval");
+        Ok(())
+    }
+
+    fn set_axes(&mut self, enabled: bool) {
+        self.dispatch(app_state::Event::SetAxes(enabled));
+    }
+
+    fn set_axis_divisions(&mut self, divisions: usize) -> Result<(), JsValue> {
+        if !(1..=20).contains(&divisions) {
+            return Err(JsValue::from_str(&format!("Axis divisions must be between 1 and 20, got {}", divisions)));
+        }
+        self.program_axes_3d.divisions = divisions;
+        self.note_untracked_change("the axis divisions");
+        Ok(())
+    }
+
+    fn set_gamepad_binding(&mut self, controller_id: &str, button: usize, action: &str) -> Result<(), JsValue> {
+        let action = gamepad::Action::from_name(action).map_err(|err| JsValue::from_str(&err))?;
+        self.gamepad.get_layout_mut(controller_id).bind(button, action);
+        Ok(())
+    }
+
+    fn set_gamepad_axes(
+        &mut self,
+        controller_id: &str,
+        move_x: usize,
+        move_y: usize,
+        look_x: usize,
+        look_y: usize,
+    ) {
+        let layout = self.gamepad.get_layout_mut(controller_id);
+        layout.move_axes = [move_x, move_y];
+        layout.look_axes = [look_x, look_y];
+    }
+
+    fn set_gamepad_deadzone(&mut self, controller_id: &str, deadzone: f32) -> Result<(), JsValue> {
+        if !(0.0..1.0).contains(&deadzone) {
+            return Err(JsValue::from_str(&format!("Gamepad deadzone must be in [0, 1), got {}", deadzone)));
+        }
+        self.gamepad.get_layout_mut(controller_id).deadzone = deadzone;
+        Ok(())
+    }
+
+    fn start_recording(&mut self) {
+        self.recording = Some(recording::Recording::new(self.state));
+    }
+
+    fn stop_recording(&mut self) -> Result<Vec<u8>, JsValue> {
+        match self.recording.take() {
+            Some(recording) => Ok(recording.encode()),
+            None => Err(JsValue::from_str("Not recording")),
+        }
+    }
+
+    fn start_replay(&mut self, log: &[u8]) -> Result<(), JsValue> {
+        let replay = recording::Recording::decode(log)
+            .and_then(recording::Replay::new)
+            .map_err(|err| JsValue::from_str(&err))?;
+        self.state = replay.initial_state();
+        self.previous_state = self.state;
+        self.replay = Some(replay);
+        Ok(())
+    }
+
+    fn stop_replay(&mut self) {
+        self.replay = None;
+    }
+
+    fn is_replaying(&self) -> bool {
+        self.replay.is_some()
+    }
+
+    /// Applies the DOM events raised since the last drain. Live input is
+    /// dropped while a replay drives the state.
+    fn drain_events(&mut self) {
+        let queued: Vec<app_state::Event> = self.events.borrow_mut().drain(..).collect();
+        if self.replay.is_some() {
+            return;
+        }
+
+        for event in queued.iter() {
+            if !self.route_to_ui(event) {
+                self.dispatch(event.clone());
+            }
+        }
+    }
+
+    fn update(&mut self, time: f32) {
+        self.previous_state = self.state;
+        self.drain_events();
+        if self.replay.is_some() {
+            self.update_replay();
+            return;
+        }
+
+        let elapsed = time - self.state.time;
+        self.apply_gamepad_input(elapsed.max(0.0));
+        self.dispatch(app_state::Event::Tick { time: time });
+
+        self.function_plot.update(time, &self.grid);
+        self.program_graph_3d.set_layout(&self.buffers, &self.grid);
+    }
+
+    fn run_frame(&mut self, timer: &mut game_loop::FixedStep, frame_time: f64) {
+        let frame = timer.advance(frame_time);
+        self.buffers.begin_frame();
+        self.frame_profiler.begin_frame(frame_time, frame.ticks);
+
+        self.frame_profiler.begin_phase();
+        for _ in 0..frame.ticks {
+            self.update(timer.tick());
+        }
+        // Paused frames draw the latest state as is, so input is applied
+        // straight away instead of piling up until the next tick.
+        if timer.is_paused() && frame.ticks == 0 {
+            self.drain_events();
+            self.previous_state = self.state;
+        }
+        if self.show_ui {
+            self.build_ui();
+        }
+        self.frame_profiler.end_phase(frame_stats::Phase::Update);
+
+        self.render(frame.alpha);
+        self.frame_profiler.end_frame(&self.buffers.stats());
+    }
+
+    fn render(&mut self, alpha: f32) {
+        self.sync_canvas_size();
+        self.frame_profiler.begin_phase();
+        // Depth writes must be on for the clear to reset the depth buffer.
+        self.render_states.apply(&self.gl, render_state::RenderState::OPAQUE);
         self.gl.clear(GL::COLOR_BUFFER_BIT | GL::DEPTH_BUFFER_BIT);
 
-        let curr_state = app_state::get_curr_state();
-
-        self.program_cube_3d.render(
-            &self.gl,
-            curr_state.control_bottom,  //bottom
-            curr_state.control_top, //top
-            curr_state.control_left,  //left
-            curr_state.control_right, //right
-            curr_state.canvas_height, //canvas_height
-            curr_state.canvas_width, //canvas_width,
-            curr_state.rotation_x_axis,
-            curr_state.rotation_y_axis,
+        let curr_state = &self.state;
+        self.program_graph_3d.show_wireframe = curr_state.show_wireframe;
+        self.program_graph_3d.show_contours = curr_state.show_contours;
+        let view = self.previous_state.get_view().lerp(&curr_state.get_view(), alpha);
+        let time = self.previous_state.time + (curr_state.time - self.previous_state.time) * alpha;
+        let viewport = common_funcs::ViewportParams {
+            bottom: curr_state.control_bottom,
+            top: curr_state.control_top,
+            left: curr_state.control_left,
+            right: curr_state.control_right,
+            canvas_height: curr_state.canvas_height,
+            canvas_width: curr_state.canvas_width,
+            view: &view,
+        };
+
+        match curr_state.scene {
+            app_state::Scene::Cube => {
+                self.program_cube_3d.render(
+                    &self.gl,
+                    &mut self.render_states,
+                    &viewport,
+                );
+            },
+            app_state::Scene::Graph if self.function_plot.is_active() => {
+                self.program_graph_3d.render(
+                    &self.gl,
+                    &mut self.render_states,
+                    &viewport,
+                    self.function_plot.y_vals().unwrap(),
+                );
+            },
+            app_state::Scene::Graph if self.gpu_heightfield && !self.program_graph_3d.needs_cpu_heights() => {
+                self.program_graph_3d.render_ripple(
+                    &self.gl,
+                    &mut self.render_states,
+                    &viewport,
+                    time,
+                );
+            },
+            app_state::Scene::Graph => {
+                self.program_graph_3d.render(
+                    &self.gl,
+                    &mut self.render_states,
+                    &viewport,
+                    &common_funcs::get_updated_3d_y_values(&self.grid, time),
+                );
+            },
+        }
+
+        self.frame_profiler.end_phase(frame_stats::Phase::Scene);
+
+        self.frame_profiler.begin_phase();
+        self.render_states.apply(&self.gl, render_state::RenderState::OVERLAY);
+        if let app_state::Scene::Graph = curr_state.scene {
+            if curr_state.show_axes {
+                let ranges = self.get_axis_ranges();
+                self.program_axes_3d.render(
+                    &self.gl,
+                    &self.program_quad_batch_2d,
+                    &self.program_text_2d,
+                    &programs::AxesParams {
+                        ranges: &ranges,
+                        layout: &self.grid,
+                        viewport: &viewport,
+                    },
+                );
+            }
+        }
+
+        if self.show_ui {
+            self.program_ui_2d.render(
+                &self.gl,
+                &self.program_quad_batch_2d,
+                &self.program_text_2d,
+                self.ui.commands(),
+                curr_state.canvas_height,
+                curr_state.canvas_width,
+            );
+        }
+        self.frame_profiler.end_phase(frame_stats::Phase::Overlay);
+
+        if self.show_perf_hud {
+            self.frame_profiler.begin_phase();
+            self.perf_hud.render(
+                &self.gl,
+                &self.program_quad_batch_2d,
+                &self.program_text_2d,
+                &self.frame_profiler.last,
+                curr_state.canvas_height,
+                curr_state.canvas_width,
+            );
+            self.frame_profiler.end_phase(frame_stats::Phase::Hud);
+        }
+    }
+}
+
+impl Client {
+    fn dispatch(&mut self, event: app_state::Event) {
+        if let Some(recording) = self.recording.as_mut() {
+            recording.record(&event);
+        }
+        self.state = self.state.apply(&event);
+    }
+
+    /// Called by every setter that changes how the scene is drawn without
+    /// going through an event, so a recording spanning it refuses to replay.
+    fn note_untracked_change(&mut self, change: &str) {
+        if let Some(recording) = self.recording.as_mut() {
+            recording.note_untracked_change(change);
+        }
+    }
+
+    /// Collects pointer edges for the next `build_ui` and keeps a press that
+    /// lands on a widget, and its release, away from the camera. Returns
+    /// whether the UI took the event.
+    fn route_to_ui(&mut self, event: &app_state::Event) -> bool {
+        if !self.show_ui {
+            return false;
+        }
+
+        match *event {
+            app_state::Event::PointerDown { id, x, y, .. } => {
+                let position = (x, self.state.canvas_height - y);
+                self.ui_input.pressed_at = Some(position);
+                if !self.ui.is_over(position.0, position.1) {
+                    return false;
+                }
+
+                // Only the cursor moves, since the camera never saw the press.
+                self.ui_pointer = Some(id);
+                self.dispatch(app_state::Event::PointerMove { id: id, x: x, y: y });
+                true
+            },
+            app_state::Event::PointerUp { id } => {
+                self.ui_input.released_at = self.state.cursor;
+                if self.ui_pointer != Some(id) {
+                    return false;
+                }
+
+                self.ui_pointer = None;
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// Sizes the drawing buffer to the canvas's CSS size in device pixels,
+    /// scaled by `render_scale`, and points the viewport at all of it.
+    fn sync_canvas_size(&self) {
+        let canvas = match self.gl.canvas().and_then(|canvas| canvas.dyn_into::<web_sys::HtmlCanvasElement>().ok()) {
+            Some(canvas) => canvas,
+            None => return,
+        };
+
+        let device_pixel_ratio = web_sys::window().map_or(1.0, |window| window.device_pixel_ratio() as f32);
+        let (buffer_height, buffer_width) = common_funcs::get_drawing_buffer_size(
+            self.state.canvas_height,
+            self.state.canvas_width,
+            device_pixel_ratio * self.render_scale,
+        );
+
+        if canvas.height() != buffer_height || canvas.width() != buffer_width {
+            canvas.set_height(buffer_height);
+            canvas.set_width(buffer_width);
+        }
+        self.gl.viewport(0, 0, buffer_width as i32, buffer_height as i32);
+    }
+
+    fn update_replay(&mut self) {
+        let mut replay = match self.replay.take() {
+            Some(replay) => replay,
+            None => return,
+        };
+
+        for event in replay.next_frame().to_vec() {
+            self.dispatch(event);
+        }
+
+        self.function_plot.update(self.state.time, &self.grid);
+        self.program_graph_3d.set_layout(&self.buffers, &self.grid);
+
+        if !replay.is_finished() {
+            self.replay = Some(replay);
+        }
+    }
+
+    fn apply_gamepad_input(&mut self, elapsed: f32) {
+        let frame = self.gamepad.poll(&gamepad::read_gamepads());
+        let seconds = elapsed / 1000.0;
+
+        if frame.look != (0.0, 0.0) {
+            let look_speed = std::f32::consts::PI * seconds;
+            self.dispatch(app_state::Event::Look {
+                rotation_x_delta: -frame.look.1 * look_speed,
+                rotation_y_delta: frame.look.0 * look_speed,
+            });
+        }
+        if frame.movement != (0.0, 0.0) {
+            self.dispatch(app_state::Event::Pan { dx: frame.movement.0 * seconds, dy: -frame.movement.1 * seconds });
+        }
+
+        for action in frame.held.iter() {
+            match action {
+                gamepad::Action::ZoomIn => self.dispatch(app_state::Event::Zoom { factor: seconds.exp() }),
+                gamepad::Action::ZoomOut => self.dispatch(app_state::Event::Zoom { factor: (-seconds).exp() }),
+                _ => {},
+            }
+        }
+
+        for &action in frame.pressed.iter() {
+            self.apply_action(action);
+        }
+    }
+
+    fn apply_action(&mut self, action: gamepad::Action) {
+        match action {
+            gamepad::Action::ResetCamera => self.dispatch(app_state::Event::ResetCamera),
+            gamepad::Action::ToggleAxes => self.dispatch(app_state::Event::SetAxes(!self.state.show_axes)),
+            gamepad::Action::ToggleContours => self.dispatch(app_state::Event::SetContours(!self.state.show_contours)),
+            gamepad::Action::ToggleScene => {
+                let scene = match self.state.scene {
+                    app_state::Scene::Cube => app_state::Scene::Graph,
+                    app_state::Scene::Graph => app_state::Scene::Cube,
+                };
+                self.dispatch(app_state::Event::SetScene(scene));
+            },
+            gamepad::Action::ToggleWireframe => self.dispatch(app_state::Event::SetWireframe(!self.state.show_wireframe)),
+            gamepad::Action::ZoomIn | gamepad::Action::ZoomOut => {},
+        }
+    }
+
+    /// Declares this frame's 2D UI and applies whatever was clicked.
+    fn build_ui(&mut self) {
+        let state = self.state;
+        self.ui.begin_frame(ui::UiInput {
+            cursor: state.cursor,
+            is_down: self.ui_pointer.is_some() || state.pointers.iter().any(|pointer| pointer.is_some()),
+            ..self.ui_input
+        });
+        self.ui_input = ui::UiInput::default();
+
+        let center_x = state.canvas_width / 2.0;
+        self.ui.set_layer(1);
+        self.ui.crosshair(center_x, state.canvas_height / 2.0, 10.0, 2.0, [1.0, 1.0, 1.0, 0.8]);
+        self.ui.set_layer(0);
+
+        let slot_size = 40.0;
+        if let Some(slot) = self.ui.hotbar(HOTBAR_ID, center_x, UI_MARGIN, slot_size, HOTBAR_SLOTS, self.hotbar_slot) {
+            self.hotbar_slot = slot;
+        }
+
+        let health_bottom = UI_MARGIN + slot_size + UI_MARGIN / 2.0;
+        let health_width = slot_size * HOTBAR_SLOTS as f32 / 2.0;
+        self.ui.health_bar(
+            ui::Rect::new(health_bottom, health_bottom + 12.0, center_x - health_width, center_x),
+            self.health,
         );
+
+        let buttons = [
+            ("Scene", gamepad::Action::ToggleScene),
+            ("Wireframe", gamepad::Action::ToggleWireframe),
+            ("Contours", gamepad::Action::ToggleContours),
+            ("Axes", gamepad::Action::ToggleAxes),
+            ("Reset", gamepad::Action::ResetCamera),
+        ];
+        let (button_width, button_height) = (140.0, 28.0);
+        let panel_right = state.canvas_width - UI_MARGIN;
+        let panel_left = panel_right - button_width - UI_MARGIN;
+        let panel_top = state.canvas_height - UI_MARGIN;
+        let panel_bottom = panel_top - (button_height + UI_MARGIN) * buttons.len() as f32 - UI_MARGIN;
+        self.ui.panel(ui::Rect::new(panel_bottom, panel_top, panel_left, panel_right), [0.05, 0.05, 0.05, 0.6]);
+
+        let (button_left, button_right) = (panel_left + UI_MARGIN / 2.0, panel_right - UI_MARGIN / 2.0);
+        let mut top = panel_top - UI_MARGIN;
+        for (index, &(label, action)) in buttons.iter().enumerate() {
+            let rect = ui::Rect::new(top - button_height, top, button_left, button_right);
+            if self.ui.button(index as u32, rect, label) {
+                self.apply_action(action);
+            }
+            top -= button_height + UI_MARGIN;
+        }
+
+        if !self.chat.is_empty() {
+            let chat_bottom = health_bottom + slot_size;
+            let chat_rect = ui::Rect::new(chat_bottom, chat_bottom + CHAT_HEIGHT, UI_MARGIN, UI_MARGIN + CHAT_WIDTH);
+            self.ui.panel(chat_rect, [0.05, 0.05, 0.05, 0.4]);
+            self.ui.push_clip(chat_rect.inset(UI_MARGIN / 2.0));
+
+            let text_width = CHAT_WIDTH - UI_MARGIN;
+            let mut bottom = chat_bottom + UI_MARGIN / 2.0;
+            for message in self.chat.iter().rev() {
+                let top = bottom + self.ui.measure_text_block(message, text_width, CHAT_SCALE);
+                self.ui.text_block(message, UI_MARGIN * 1.5, top, text_width, CHAT_SCALE);
+                bottom = top;
+            }
+            self.ui.pop_clip();
+        }
+    }
+
+    fn get_axis_ranges(&self) -> programs::AxisRanges {
+        match self.function_plot.y_vals() {
+            Some(y_vals) => programs::AxisRanges {
+                x: (self.function_plot.x_min, self.function_plot.x_max),
+                y: color_map::get_y_range(y_vals),
+                z: (self.function_plot.z_min, self.function_plot.z_max),
+                y_scale: self.function_plot.y_scale,
+            },
+            None => programs::AxisRanges {
+                x: (self.grid.x_min, self.grid.x_max),
+                y: (-constants::RIPPLE_AMPLITUDE, constants::RIPPLE_AMPLITUDE),
+                z: (self.grid.z_min, self.grid.z_max),
+                y_scale: 1.0,
+            },
+        }
     }
 }
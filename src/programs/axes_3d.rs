@@ -0,0 +1,178 @@
+use web_sys::WebGlRenderingContext as GL;
+use web_sys::*;
+use super::super::buffer_pool::{BufferPool, MeshHandle};
+use super::super::common_funcs as cf;
+use super::super::grid::GridLayout;
+use super::super::quad_batch::QuadBatch;
+use super::{QuadBatch2D, Text2D};
+
+const TICK_LENGTH: f32 = 0.05;
+const LABEL_SCALE: f32 = 2.0;
+const LABEL_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+pub struct AxisRanges {
+    pub x: (f32, f32),
+    pub y: (f32, f32),
+    pub z: (f32, f32),
+    pub y_scale: f32,
+}
+
+/// What one frame of axes is drawn for: the plotted ranges and grid, placed
+/// in the same viewport as the graph itself.
+pub struct AxesParams<'a> {
+    pub ranges: &'a AxisRanges,
+    pub layout: &'a GridLayout,
+    pub viewport: &'a cf::ViewportParams<'a>,
+}
+
+pub struct Axes3D {
+    program: WebGlProgram,
+    pub divisions: usize,
+    grid_mesh: MeshHandle,
+    axes_mesh: MeshHandle,
+    labels: QuadBatch,
+    u_color: WebGlUniformLocation,
+    u_opacity: WebGlUniformLocation,
+    u_projection: WebGlUniformLocation,
+    u_y_offset: WebGlUniformLocation,
+}
+
+fn format_tick(value: f32, step: f32) -> String {
+    let decimals = if step >= 1.0 || step <= 0.0 {
+        0
+    } else {
+        (-step.log10()).ceil() as usize
+    };
+
+    let label = format!("{:.*}", decimals, value);
+    if label.starts_with('-') && label[1..].chars().all(|c| c == '0' || c == '.') {
+        label[1..].to_string()
+    } else {
+        label
+    }
+}
+
+impl Axes3D {
+    pub fn new(gl: &GL, buffers: &BufferPool) -> Self {
+        let program = cf::link_program(
+            gl,
+            super::super::shaders::vertex::graph_3d_lines::SHADER,
+            super::super::shaders::fragment::uniform_color::SHADER,
+            &["aPosition", "aY"],
+        ).unwrap();
+
+        Self {
+            divisions: 4,
+            grid_mesh: MeshHandle::new(0).with_attribute(0, 3, buffers.create_dynamic_array_buffer(0)),
+            axes_mesh: MeshHandle::new(0).with_attribute(0, 3, buffers.create_dynamic_array_buffer(0)),
+            labels: QuadBatch::new(),
+            u_color: gl.get_uniform_location(&program, "uColor").unwrap(),
+            u_opacity: gl.get_uniform_location(&program, "uOpacity").unwrap(),
+            u_projection: gl.get_uniform_location(&program, "uProjection").unwrap(),
+            u_y_offset: gl.get_uniform_location(&program, "uYOffset").unwrap(),
+            program: program,
+        }
+    }
+
+    fn draw_lines(&self, gl: &GL, mesh: &MeshHandle, lines: &[f32], color: [f32; 4]) {
+        mesh.attribute(0).unwrap().write_f32(lines);
+        gl.uniform4f(Some(&self.u_color), color[0], color[1], color[2], color[3]);
+
+        mesh.bind(gl);
+        gl.vertex_attrib1f(1, 0.0);
+        mesh.draw_count(gl, GL::LINES, (lines.len() / 3) as i32);
+    }
+
+    /// Draws the grid and axes as lines, then every tick label in one batch.
+    pub fn render(&mut self, gl: &GL, quads: &QuadBatch2D, text: &Text2D, params: &AxesParams) {
+        let (ranges, layout, viewport) = (params.ranges, params.layout, params.viewport);
+        let my_3d_matrices = cf::get_3d_matrices(viewport);
+
+        let (y_min, y_max) = if ranges.y.1 > ranges.y.0 {
+            ranges.y
+        } else {
+            (ranges.y.0 - 0.5, ranges.y.0 + 0.5)
+        };
+        let floor = y_min;
+        let y_scale = if ranges.y_scale != 0.0 { ranges.y_scale } else { 1.0 };
+
+        let (x0, x1, z0, z1) = (layout.x_min, layout.x_max, layout.z_min, layout.z_max);
+
+        let mut grid: Vec<f32> = Vec::new();
+        let mut axes: Vec<f32> = vec![
+            x0, floor, z0, x1, floor, z0,
+            x0, floor, z0, x0, floor, z1,
+            x0, floor, z0, x0, y_max, z0,
+        ];
+        let mut labels: Vec<(String, [f32; 3])> = vec![
+            (String::from("x"), [x1 + 3.0 * TICK_LENGTH, floor, z0]),
+            (String::from("y"), [x0, y_max + 3.0 * TICK_LENGTH, z0]),
+            (String::from("z"), [x0, floor, z1 + 3.0 * TICK_LENGTH]),
+        ];
+
+        let divisions = self.divisions.max(1);
+        let x_step = (ranges.x.1 - ranges.x.0) / divisions as f32;
+        let y_step = (y_max - y_min) / y_scale / divisions as f32;
+        let z_step = (ranges.z.1 - ranges.z.0) / divisions as f32;
+
+        for i in 0..=divisions {
+            let t = i as f32 / divisions as f32;
+            let x = x0 + t * (x1 - x0);
+            let y = y_min + t * (y_max - y_min);
+            let z = z0 + t * (z1 - z0);
+
+            grid.extend_from_slice(&[x, floor, z0, x, floor, z1]);
+            grid.extend_from_slice(&[x0, floor, z, x1, floor, z]);
+
+            axes.extend_from_slice(&[x, floor, z0, x, floor, z0 - TICK_LENGTH]);
+            axes.extend_from_slice(&[x0, floor, z, x0 - TICK_LENGTH, floor, z]);
+            axes.extend_from_slice(&[x0, y, z0, x0 - TICK_LENGTH, y, z0 - TICK_LENGTH]);
+
+            labels.push((
+                format_tick(ranges.x.0 + t * (ranges.x.1 - ranges.x.0), x_step),
+                [x, floor, z0 - 2.5 * TICK_LENGTH],
+            ));
+            labels.push((
+                format_tick(ranges.z.0 + t * (ranges.z.1 - ranges.z.0), z_step),
+                [x0 - 2.5 * TICK_LENGTH, floor, z],
+            ));
+            labels.push((
+                format_tick(y / y_scale, y_step.abs()),
+                [x0 - 2.5 * TICK_LENGTH, y, z0 - 2.5 * TICK_LENGTH],
+            ));
+        }
+
+        gl.use_program(Some(&self.program));
+        gl.uniform_matrix4fv_with_f32_array(Some(&self.u_projection), false, &my_3d_matrices.projection);
+        gl.uniform1f(Some(&self.u_y_offset), 0.0);
+        gl.uniform1f(Some(&self.u_opacity), 1.0);
+
+        self.draw_lines(gl, &self.grid_mesh, &grid, [0.5, 0.5, 0.5, 0.35]);
+        self.draw_lines(gl, &self.axes_mesh, &axes, [1.0, 1.0, 1.0, 1.0]);
+
+        self.labels.clear();
+        for (label, position) in labels.iter() {
+            let clip = cf::transform_point(&my_3d_matrices.projection, position[0], position[1], position[2]);
+            if clip[3] <= 0.0 {
+                continue;
+            }
+
+            let x = (clip[0] / clip[3] + 1.0) / 2.0 * viewport.canvas_width;
+            let y = (clip[1] / clip[3] + 1.0) / 2.0 * viewport.canvas_height;
+            if x < viewport.left || x > viewport.right || y < viewport.bottom || y > viewport.top {
+                continue;
+            }
+
+            self.labels.text(
+                text.font(),
+                label,
+                x - text.get_text_width(label, LABEL_SCALE) / 2.0,
+                y + text.get_line_height(LABEL_SCALE) / 2.0,
+                LABEL_SCALE,
+                None,
+                LABEL_COLOR,
+            );
+        }
+        quads.render(gl, &self.labels, text.atlas_texture(), 1.0, viewport.canvas_height, viewport.canvas_width);
+    }
+}
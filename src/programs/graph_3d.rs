@@ -1,103 +1,190 @@
 use crate::constants::*;
-use wasm_bindgen::JsCast;
+use std::cell::Cell;
 use web_sys::WebGlRenderingContext as GL;
 use web_sys::*;
-use js_sys::WebAssembly;
+use super::super::buffer_pool::{BufferPool, MeshHandle};
+use super::super::color_map::{self, ColorMap};
 use super::super::common_funcs as cf;
+use super::super::contours;
+use super::super::grid::{GridLayout, Shading};
+use super::super::render_state::{Cull, RenderState, RenderStateTracker};
+
+const COLOR_MAP_TEXTURE_WIDTH: usize = 256;
+const OVERLAY_Y_OFFSET: f32 = 0.002;
+// The surface is seen from both sides, and pushed back so the overlay lines
+// on it stay visible.
+const SURFACE_STATE: RenderState = RenderState::OPAQUE.with_cull(Cull::None).with_depth_offset();
 
 pub struct Graph3D {
     pub program: WebGlProgram,
-    pub indices_buffer: WebGlBuffer,
-    pub index_count: i32,
-    pub normals_buffer: WebGlBuffer,
-    pub position_buffer: WebGlBuffer,
+    pub color_map: ColorMap,
+    pub color_map_texture: WebGlTexture,
+    pub color_range: Option<(f32, f32)>,
+    pub last_color_range: Cell<(f32, f32)>,
+    pub layout: GridLayout,
+    pub shading: Shading,
+    pub mesh: MeshHandle,
+    pub wireframe_mesh: MeshHandle,
+    pub contour_mesh: MeshHandle,
+    pub show_wireframe: bool,
+    pub show_contours: bool,
+    pub contour_interval: f32,
+    pub u_color_range: WebGlUniformLocation,
     pub u_normals_rotation: WebGlUniformLocation,
     pub u_opacity: WebGlUniformLocation,
     pub u_projection: WebGlUniformLocation,
-    pub y_buffer: WebGlBuffer,
+    pub lines_program: WebGlProgram,
+    pub u_lines_color: WebGlUniformLocation,
+    pub u_lines_opacity: WebGlUniformLocation,
+    pub u_lines_projection: WebGlUniformLocation,
+    pub u_lines_y_offset: WebGlUniformLocation,
+    pub ripple_program: WebGlProgram,
+    pub u_ripple_amplitude: WebGlUniformLocation,
+    pub u_ripple_color_range: WebGlUniformLocation,
+    pub u_ripple_frequency: WebGlUniformLocation,
+    pub u_ripple_normals_rotation: WebGlUniformLocation,
+    pub u_ripple_opacity: WebGlUniformLocation,
+    pub u_ripple_projection: WebGlUniformLocation,
+    pub u_ripple_time: WebGlUniformLocation,
 }
 
 impl Graph3D {
-    pub fn new(gl: &GL) -> Self {
+    pub fn new(gl: &GL, buffers: &BufferPool) -> Self {
         let program = cf::link_program(
             &gl,
             &super::super::shaders::vertex::graph_3d::SHADER,
-            &super::super::shaders::fragment::varying_color_from_vertex::SHADER
+            &super::super::shaders::fragment::color_map_lighting::SHADER,
+            &["aPosition", "aY", "aVertexNormal"],
         ).unwrap();
 
-        let (positions, indices) = cf::get_position_grid_n_by_n(GRID_SIZE);
-        let memory_buffer = wasm_bindgen::memory()
-            .dyn_into::<WebAssembly::Memory>()
-            .unwrap()
-            .buffer();
+        let ripple_program = cf::link_program(
+            &gl,
+            &super::super::shaders::vertex::graph_3d_ripple::SHADER,
+            &super::super::shaders::fragment::color_map_lighting::SHADER,
+            &["aPosition"],
+        ).unwrap();
 
-        let vertices_location = positions.as_ptr() as u32 / 4;
-        let vert_array = js_sys::Float32Array::new(&memory_buffer).subarray(
-            vertices_location,
-            vertices_location + positions.len() as u32,
-        );
+        let lines_program = cf::link_program(
+            &gl,
+            &super::super::shaders::vertex::graph_3d_lines::SHADER,
+            &super::super::shaders::fragment::uniform_color::SHADER,
+            &["aPosition", "aY"],
+        ).unwrap();
 
-        let buffer_position = gl.create_buffer().ok_or("Failed to create buffer").unwrap();
-        gl.bind_buffer(GL::ARRAY_BUFFER, Some(&buffer_position));
-        gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &vert_array, GL::STATIC_DRAW);
-
-        let indices_memory_buffer = wasm_bindgen::memory()
-            .dyn_into::<WebAssembly::Memory>()
-            .unwrap()
-            .buffer();
-        let indices_location = indices.as_ptr() as u32 / 2;
-        let indices_array = js_sys::Uint16Array::new(&indices_memory_buffer).subarray(
-            indices_location,
-            indices_location + indices.len() as u32
-        );
+        let layout = GridLayout::default();
+        let (mesh, wireframe_mesh) = Self::create_grid_meshes(buffers, &layout);
 
-        let buffer_indices = gl.create_buffer().unwrap();
-        gl.bind_buffer(GL::ELEMENT_ARRAY_BUFFER, Some(&buffer_indices));
-        gl.buffer_data_with_array_buffer_view(
-            GL::ELEMENT_ARRAY_BUFFER,
-            &indices_array,
-            GL::STATIC_DRAW,
-        );
+        let color_map = ColorMap::from_name("flat").unwrap();
+        let color_map_texture = cf::create_texture(gl, GL::LINEAR);
 
-        Self {
+        let graph = Self {
+            color_map: color_map,
+            color_map_texture: color_map_texture,
+            color_range: None,
+            last_color_range: Cell::new((0.0, 0.0)),
+            u_color_range: gl.get_uniform_location(&program, "uColorRange").unwrap(),
             u_normals_rotation: gl.get_uniform_location(&program, "uNormalsRotation").unwrap(),
             u_opacity: gl.get_uniform_location(&program, "uOpacity").unwrap(),
             u_projection: gl.get_uniform_location(&program, "uProjection").unwrap(),
             program: program,
+            u_lines_color: gl.get_uniform_location(&lines_program, "uColor").unwrap(),
+            u_lines_opacity: gl.get_uniform_location(&lines_program, "uOpacity").unwrap(),
+            u_lines_projection: gl.get_uniform_location(&lines_program, "uProjection").unwrap(),
+            u_lines_y_offset: gl.get_uniform_location(&lines_program, "uYOffset").unwrap(),
+            lines_program: lines_program,
+            u_ripple_amplitude: gl.get_uniform_location(&ripple_program, "uAmplitude").unwrap(),
+            u_ripple_color_range: gl.get_uniform_location(&ripple_program, "uColorRange").unwrap(),
+            u_ripple_frequency: gl.get_uniform_location(&ripple_program, "uFrequency").unwrap(),
+            u_ripple_normals_rotation: gl.get_uniform_location(&ripple_program, "uNormalsRotation").unwrap(),
+            u_ripple_opacity: gl.get_uniform_location(&ripple_program, "uOpacity").unwrap(),
+            u_ripple_projection: gl.get_uniform_location(&ripple_program, "uProjection").unwrap(),
+            u_ripple_time: gl.get_uniform_location(&ripple_program, "uTime").unwrap(),
+            ripple_program: ripple_program,
+            layout: layout,
+            shading: Shading::Smooth,
+            mesh: mesh,
+            wireframe_mesh: wireframe_mesh,
+            contour_mesh: MeshHandle::new(0).with_attribute(0, 3, buffers.create_dynamic_array_buffer(0)),
+            show_wireframe: false,
+            show_contours: false,
+            contour_interval: 0.05,
+        };
+        graph.upload_color_map(gl);
+        graph
+    }
+
+    pub fn set_color_map(&mut self, gl: &GL, color_map: ColorMap) {
+        self.color_map = color_map;
+        self.upload_color_map(gl);
+    }
+
+    fn upload_color_map(&self, gl: &GL) {
+        let pixels = self.color_map.get_texture_data(COLOR_MAP_TEXTURE_WIDTH);
+        cf::upload_texture_rgba(gl, &self.color_map_texture, COLOR_MAP_TEXTURE_WIDTH, 1, &pixels);
+    }
+
+    fn bind_color_map(&self, gl: &GL, u_color_range: &WebGlUniformLocation, auto_range: (f32, f32)) {
+        let (min, max) = self.color_range.unwrap_or(auto_range);
+        self.last_color_range.set((min, max));
+
+        gl.active_texture(GL::TEXTURE0);
+        gl.bind_texture(GL::TEXTURE_2D, Some(&self.color_map_texture));
+        gl.uniform2f(Some(u_color_range), min, max);
+    }
+
+    fn create_grid_meshes(buffers: &BufferPool, layout: &GridLayout) -> (MeshHandle, MeshHandle) {
+        let (positions, indices) = cf::get_position_grid(layout);
+        let edges = cf::get_edge_indices(&indices);
+
+        let position_buffer = buffers.create_array_buffer(&positions, GL::STATIC_DRAW);
+        let y_buffer = buffers.create_dynamic_array_buffer(positions.len() / 3);
+
+        let mesh = MeshHandle::new(indices.len() as i32)
+            .with_attribute(0, 3, position_buffer.clone())
+            .with_attribute(1, 1, y_buffer.clone())
+            .with_attribute(2, 3, buffers.create_dynamic_array_buffer(positions.len()))
+            .with_indices(buffers.create_index_buffer(&indices, GL::STATIC_DRAW));
 
-            normals_buffer: gl.create_buffer().ok_or("Failed to create buffer").unwrap(),
-            position_buffer: buffer_position,
-            indices_buffer: buffer_indices,
-            index_count: indices_array.length() as i32,
-            y_buffer: gl.create_buffer().ok_or("failed to create buffer").unwrap(),
+        let wireframe_mesh = MeshHandle::new(edges.len() as i32)
+            .with_attribute(0, 3, position_buffer)
+            .with_attribute(1, 1, y_buffer)
+            .with_indices(buffers.create_index_buffer(&edges, GL::STATIC_DRAW));
+
+        (mesh, wireframe_mesh)
+    }
+
+    pub fn has_overlays(&self) -> bool {
+        self.show_wireframe || self.show_contours
+    }
+
+    pub fn needs_cpu_heights(&self) -> bool {
+        self.has_overlays() || self.shading == Shading::Flat
+    }
+
+    pub fn set_layout(&mut self, buffers: &BufferPool, layout: &GridLayout) {
+        if *layout == self.layout {
+            return;
         }
-    } 
+
+        self.mesh.delete();
+        self.wireframe_mesh.delete();
+
+        let (mesh, wireframe_mesh) = Self::create_grid_meshes(buffers, layout);
+        self.mesh = mesh;
+        self.wireframe_mesh = wireframe_mesh;
+        self.layout = *layout;
+    }
 
     pub fn render(
         &self,
         gl: &GL,
-        bottom: f32,
-        top: f32,
-        left: f32,
-        right: f32,
-        canvas_height: f32,
-        canvas_width: f32,
-        rotation_angle_x_axis: f32,
-        rotation_angle_y_axis: f32,
-        y_vals: &Vec<f32>,
+        states: &mut RenderStateTracker,
+        viewport: &cf::ViewportParams,
+        y_vals: &[f32],
     ) {
         gl.use_program(Some(&self.program));
 
-        let my_3d_matrices = cf::get_3d_matrices(
-            bottom,
-            top,
-            left,
-            right,
-            canvas_height,
-            canvas_width,
-            rotation_angle_x_axis,
-            rotation_angle_y_axis,
-        );
+        let my_3d_matrices = cf::get_3d_matrices(viewport);
 
         gl.uniform_matrix4fv_with_f32_array(
             Some(&self.u_projection),
@@ -110,49 +197,79 @@ impl Graph3D {
             &my_3d_matrices.normals_rotation,
         );
         gl.uniform1f(Some(&self.u_opacity), 1.0);
+        self.bind_color_map(gl, &self.u_color_range, color_map::get_y_range(y_vals));
 
-        gl.bind_buffer(GL::ARRAY_BUFFER, Some(&self.position_buffer));
-        gl.vertex_attrib_pointer_with_i32(0, 3, GL::FLOAT, false, 0, 0);
-        gl.enable_vertex_attrib_array(0);
-
-        gl.bind_buffer(GL::ARRAY_BUFFER, Some(&self.y_buffer));
-        gl.vertex_attrib_pointer_with_i32(1, 1, GL::FLOAT, false, 0, 0);
-        gl.enable_vertex_attrib_array(1);
-
-        let y_memory_buffer = wasm_bindgen::memory()
-            .dyn_into::<WebAssembly::Memory>()
-            .unwrap()
-            .buffer();
-        let y_location = y_vals.as_ptr() as u32 / 4;
-        let y_array = js_sys::Float32Array::new(&y_memory_buffer).subarray(
-            y_location,
-            y_location + y_vals.len() as u32,
-        );
-        gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &y_array, GL::DYNAMIC_DRAW);
-
-        gl.bind_buffer(GL::ARRAY_BUFFER, Some(&self.normals_buffer));
-        gl.vertex_attrib_pointer_with_i32(2, 3, GL::FLOAT, false, 0, 0);
-        gl.enable_vertex_attrib_array(2);
-
-        let normals_vals = cf::get_grid_normals(super::super::constants::GRID_SIZE, &y_vals);
-        let normals_vals_memory_buffer = wasm_bindgen::memory()
-            .dyn_into::<WebAssembly::Memory>()
-            .unwrap()
-            .buffer();
-
-        let normals_vals_location = normals_vals.as_ptr() as u32 / 4;
-        let normals_vals_array = js_sys::Float32Array::new(&normals_vals_memory_buffer).subarray(
-            normals_vals_location,
-            normals_vals_location + normals_vals.len() as u32
+        self.mesh.attribute(1).unwrap().write_f32(y_vals);
+
+        let normals_vals = cf::get_grid_normals(&self.layout, y_vals, self.shading);
+        self.mesh.attribute(2).unwrap().write_f32(&normals_vals);
+
+        states.apply(gl, SURFACE_STATE);
+        self.mesh.bind(gl);
+        self.mesh.draw(gl, GL::TRIANGLES);
+
+        if self.has_overlays() {
+            self.render_overlays(gl, states, &my_3d_matrices.projection, y_vals);
+        }
+    }
+
+    fn render_overlays(&self, gl: &GL, states: &mut RenderStateTracker, projection: &[f32; 16], y_vals: &[f32]) {
+        gl.use_program(Some(&self.lines_program));
+        gl.uniform_matrix4fv_with_f32_array(Some(&self.u_lines_projection), false, projection);
+        gl.uniform1f(Some(&self.u_lines_y_offset), OVERLAY_Y_OFFSET);
+
+        if self.show_wireframe {
+            gl.uniform4f(Some(&self.u_lines_color), 0.1, 0.1, 0.1, 1.0);
+            gl.uniform1f(Some(&self.u_lines_opacity), 0.5);
+            states.apply(gl, RenderState::TRANSPARENT.with_cull(Cull::None));
+
+            self.wireframe_mesh.bind(gl);
+            self.wireframe_mesh.draw(gl, GL::LINES);
+        }
+
+        if self.show_contours {
+            let lines = contours::get_contour_lines(&self.layout, y_vals, self.contour_interval);
+            self.contour_mesh.attribute(0).unwrap().write_f32(&lines);
+
+            gl.uniform4f(Some(&self.u_lines_color), 1.0, 1.0, 1.0, 1.0);
+            gl.uniform1f(Some(&self.u_lines_opacity), 1.0);
+            states.apply(gl, RenderState::OPAQUE.with_cull(Cull::None));
+
+            self.contour_mesh.bind(gl);
+            gl.vertex_attrib1f(1, 0.0);
+            self.contour_mesh.draw_count(gl, GL::LINES, (lines.len() / 3) as i32);
+        }
+    }
+
+    pub fn render_ripple(
+        &self,
+        gl: &GL,
+        states: &mut RenderStateTracker,
+        viewport: &cf::ViewportParams,
+        curr_time: f32,
+    ) {
+        gl.use_program(Some(&self.ripple_program));
+
+        let my_3d_matrices = cf::get_3d_matrices(viewport);
+
+        gl.uniform_matrix4fv_with_f32_array(
+            Some(&self.u_ripple_projection),
+            false,
+            &my_3d_matrices.projection,
         );
-        gl.bind_buffer(GL::ARRAY_BUFFER, Some(&self.normals_buffer));
-        gl.buffer_data_with_array_buffer_view(
-            GL::ARRAY_BUFFER,
-            &normals_vals_array,
-            GL::DYNAMIC_DRAW,
+        gl.uniform_matrix4fv_with_f32_array(
+            Some(&self.u_ripple_normals_rotation),
+            false,
+            &my_3d_matrices.normals_rotation,
         );
+        gl.uniform1f(Some(&self.u_ripple_opacity), 1.0);
+        gl.uniform1f(Some(&self.u_ripple_amplitude), RIPPLE_AMPLITUDE);
+        gl.uniform1f(Some(&self.u_ripple_frequency), FREQ * std::f32::consts::PI);
+        gl.uniform1f(Some(&self.u_ripple_time), curr_time * RIPPLE_TIME_SCALE);
+        self.bind_color_map(gl, &self.u_ripple_color_range, (-RIPPLE_AMPLITUDE, RIPPLE_AMPLITUDE));
 
-        gl.draw_elements_with_i32(GL::TRIANGLES, self.index_count, GL::UNSIGNED_SHORT, 0);
-
+        states.apply(gl, SURFACE_STATE);
+        self.mesh.bind(gl);
+        self.mesh.draw(gl, GL::TRIANGLES);
     }
-}
\ No newline at end of file
+}
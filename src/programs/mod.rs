@@ -1,11 +1,20 @@
-mod color_2d;
-pub use color_2d::*;
-
-mod color_2d_gradient;
-pub use color_2d_gradient::*;
-
 mod graph_3d;
 pub use graph_3d::*;
 
 mod cube_3d;
-pub use cube_3d::*;
\ No newline at end of file
+pub use cube_3d::*;
+
+mod text_2d;
+pub use text_2d::*;
+
+mod axes_3d;
+pub use axes_3d::*;
+
+mod perf_hud;
+pub use perf_hud::*;
+
+mod ui_2d;
+pub use ui_2d::*;
+
+mod quad_batch_2d;
+pub use quad_batch_2d::*;
\ No newline at end of file
@@ -0,0 +1,78 @@
+use web_sys::WebGlRenderingContext as GL;
+use super::super::frame_stats::FrameStats;
+use super::super::quad_batch::QuadBatch;
+use super::super::ui::Rect;
+use super::{QuadBatch2D, Text2D};
+
+const MARGIN: f32 = 10.0;
+const BAR_HEIGHT: f32 = 12.0;
+const BAR_WIDTH: f32 = 200.0;
+const ROW_SPACING: f32 = 4.0;
+const LABEL_SCALE: f32 = 1.5;
+
+const BACKGROUND_COLOR: [f32; 4] = [0.15, 0.15, 0.15, 0.8];
+const TEXT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+/// Frame time bars scaled against a frame budget, plus a summary line of
+/// draw counts, in the top left corner.
+pub struct PerfHud {
+    pub budget_ms: f32,
+    batch: QuadBatch,
+}
+
+impl PerfHud {
+    pub fn new() -> Self {
+        Self {
+            budget_ms: 1000.0 / 60.0,
+            batch: QuadBatch::new(),
+        }
+    }
+
+    pub fn render(
+        &mut self,
+        gl: &GL,
+        quads: &QuadBatch2D,
+        text: &Text2D,
+        stats: &FrameStats,
+        canvas_height: f32,
+        canvas_width: f32,
+    ) {
+        let rows = [
+            ("frame", stats.frame_ms, [0.6, 0.6, 0.6, 1.0]),
+            ("update", stats.update_ms, [0.3, 0.6, 1.0, 1.0]),
+            ("scene", stats.scene_ms, [0.3, 0.9, 0.4, 1.0]),
+            ("overlay", stats.overlay_ms, [1.0, 0.8, 0.2, 1.0]),
+            ("hud", stats.hud_ms, [1.0, 0.4, 0.4, 1.0]),
+        ];
+
+        self.batch.clear();
+        let font = text.font();
+        let mut top = canvas_height - MARGIN;
+        for &(name, ms, color) in rows.iter() {
+            let bottom = top - BAR_HEIGHT;
+            let filled = (ms / self.budget_ms).clamp(0.0, 1.0) * BAR_WIDTH;
+
+            self.batch.rect(Rect::new(bottom, top, MARGIN, MARGIN + BAR_WIDTH), BACKGROUND_COLOR);
+            if filled > 0.0 {
+                self.batch.rect(Rect::new(bottom, top, MARGIN, MARGIN + filled), color);
+            }
+
+            let label = format!("{} {:.2} ms", name, ms);
+            let label_top = top - (BAR_HEIGHT - text.get_line_height(LABEL_SCALE)) / 2.0;
+            self.batch.text(font, &label, MARGIN + BAR_WIDTH + MARGIN, label_top, LABEL_SCALE, None, TEXT_COLOR);
+
+            top = bottom - ROW_SPACING;
+        }
+
+        let summary = format!(
+            "{} ticks  {} draws  {} tris  {:.1} kB",
+            stats.ticks,
+            stats.draw_calls,
+            stats.triangles,
+            stats.bytes_uploaded as f32 / 1024.0,
+        );
+        self.batch.text(font, &summary, MARGIN, top, LABEL_SCALE, None, TEXT_COLOR);
+
+        quads.render(gl, &self.batch, text.atlas_texture(), 1.0, canvas_height, canvas_width);
+    }
+}
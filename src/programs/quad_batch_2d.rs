@@ -0,0 +1,67 @@
+use web_sys::WebGlRenderingContext as GL;
+use web_sys::*;
+use super::super::buffer_pool::{BufferPool, MeshHandle};
+use super::super::common_funcs as cf;
+use super::super::quad_batch::QuadBatch;
+
+/// Draws a whole `QuadBatch` with one draw call, streaming its vertices into
+/// buffers that are reused from frame to frame.
+pub struct QuadBatch2D {
+    program: WebGlProgram,
+    quad_mesh: MeshHandle,
+    u_canvas_size: WebGlUniformLocation,
+    u_opacity: WebGlUniformLocation,
+}
+
+impl QuadBatch2D {
+    pub fn new(gl: &GL, buffers: &BufferPool) -> Self {
+        let program = cf::link_program(
+            gl,
+            super::super::shaders::vertex::quad_batch_2d::SHADER,
+            super::super::shaders::fragment::quad_batch_2d::SHADER,
+            &["aPosition", "aTexCoord", "aColor"],
+        ).unwrap();
+
+        let quad_mesh = MeshHandle::new(0)
+            .with_attribute(0, 2, buffers.create_dynamic_array_buffer(0))
+            .with_attribute(1, 4, buffers.create_dynamic_array_buffer(0))
+            .with_attribute(2, 4, buffers.create_dynamic_array_buffer(0));
+
+        Self {
+            u_canvas_size: gl.get_uniform_location(&program, "uCanvasSize").unwrap(),
+            u_opacity: gl.get_uniform_location(&program, "uOpacity").unwrap(),
+            quad_mesh: quad_mesh,
+            program: program,
+        }
+    }
+
+    pub fn render(
+        &self,
+        gl: &GL,
+        batch: &QuadBatch,
+        atlas: &WebGlTexture,
+        opacity: f32,
+        canvas_height: f32,
+        canvas_width: f32,
+    ) {
+        let vertices = batch.build();
+        if vertices.vertex_count() == 0 {
+            return;
+        }
+
+        gl.use_program(Some(&self.program));
+
+        self.quad_mesh.attribute(0).unwrap().write_f32(&vertices.positions);
+        self.quad_mesh.attribute(1).unwrap().write_f32(&vertices.tex_coords);
+        self.quad_mesh.attribute(2).unwrap().write_f32(&vertices.colors);
+
+        gl.uniform2f(Some(&self.u_canvas_size), canvas_width, canvas_height);
+        gl.uniform1f(Some(&self.u_opacity), opacity);
+
+        gl.active_texture(GL::TEXTURE0);
+        gl.bind_texture(GL::TEXTURE_2D, Some(atlas));
+
+        self.quad_mesh.bind(gl);
+        self.quad_mesh.draw_count(gl, GL::TRIANGLES, vertices.vertex_count() as i32);
+    }
+}
@@ -0,0 +1,55 @@
+use std::rc::Rc;
+use web_sys::WebGlRenderingContext as GL;
+use web_sys::*;
+use super::super::common_funcs as cf;
+use super::super::font::{Font, FontKind};
+
+/// The current font and its atlas texture; text itself is drawn through a
+/// `QuadBatch`.
+pub struct Text2D {
+    atlas_texture: WebGlTexture,
+    font: Rc<Font>,
+}
+
+fn upload_font_atlas(gl: &GL, font: &Font) -> WebGlTexture {
+    let filter = match font.kind {
+        FontKind::Bitmap => GL::NEAREST,
+        FontKind::Sdf { .. } => GL::LINEAR,
+    };
+
+    let texture = cf::create_texture(gl, filter);
+    cf::upload_texture_rgba(gl, &texture, font.atlas_width, font.atlas_height, &font.atlas_pixels);
+    texture
+}
+
+impl Text2D {
+    pub fn new(gl: &GL) -> Self {
+        let font = Rc::new(Font::builtin());
+        Self {
+            atlas_texture: upload_font_atlas(gl, &font),
+            font: font,
+        }
+    }
+
+    pub fn font(&self) -> &Rc<Font> {
+        &self.font
+    }
+
+    pub fn set_font(&mut self, gl: &GL, font: Rc<Font>) {
+        gl.delete_texture(Some(&self.atlas_texture));
+        self.atlas_texture = upload_font_atlas(gl, &font);
+        self.font = font;
+    }
+
+    pub fn atlas_texture(&self) -> &WebGlTexture {
+        &self.atlas_texture
+    }
+
+    pub fn get_text_width(&self, text: &str, scale: f32) -> f32 {
+        self.font.measure(text, scale)
+    }
+
+    pub fn get_line_height(&self, scale: f32) -> f32 {
+        self.font.line_height * scale
+    }
+}
@@ -0,0 +1,46 @@
+use web_sys::WebGlRenderingContext as GL;
+use super::super::quad_batch::QuadBatch;
+use super::super::ui::DrawCommand;
+use super::{QuadBatch2D, Text2D};
+
+/// Draws the commands queued by `ui::Ui` in order, over whatever the 3D pass
+/// left in the frame, as a single batch.
+pub struct Ui2D {
+    pub opacity: f32,
+    batch: QuadBatch,
+}
+
+impl Ui2D {
+    pub fn new() -> Self {
+        Self {
+            opacity: 1.0,
+            batch: QuadBatch::new(),
+        }
+    }
+
+    pub fn render(
+        &mut self,
+        gl: &GL,
+        quads: &QuadBatch2D,
+        text: &Text2D,
+        commands: &[DrawCommand],
+        canvas_height: f32,
+        canvas_width: f32,
+    ) {
+        self.batch.clear();
+        for command in commands.iter() {
+            match command {
+                DrawCommand::Rect { rect, color } => self.batch.rect(*rect, *color),
+                DrawCommand::Gradient { rect, colors } => self.batch.gradient(*rect, *colors),
+                DrawCommand::Text { text: label, left, top, scale, max_width, color } => {
+                    self.batch.text(text.font(), label, *left, *top, *scale, *max_width, *color);
+                },
+                DrawCommand::PushClip { rect } => self.batch.push_clip(*rect),
+                DrawCommand::PopClip => self.batch.pop_clip(),
+                DrawCommand::Layer { z } => self.batch.set_z(*z),
+            }
+        }
+
+        quads.render(gl, &self.batch, text.atlas_texture(), self.opacity, canvas_height, canvas_width);
+    }
+}
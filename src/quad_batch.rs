@@ -0,0 +1,236 @@
+use super::font::{Font, FontKind};
+use super::ui::Rect;
+
+/// How a quad's colour is masked. The atlas is whichever texture the batch is
+/// drawn with, normally the current font's.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Fill {
+    Solid,
+    Bitmap { uvs: (f32, f32, f32, f32) },
+    Sdf { uvs: (f32, f32, f32, f32), smoothing: f32 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Quad {
+    rect: Rect,
+    fill: Fill,
+    /// Corner colours in the order top left, bottom left, top right, bottom right.
+    colors: [[f32; 4]; 4],
+    z: i32,
+    clip: Option<Rect>,
+}
+
+/// Per-vertex attributes for two triangles per quad. Texture coordinates
+/// carry the fill mode (0 solid, 1 bitmap, 2 SDF) and SDF smoothing in `z`
+/// and `w`.
+#[derive(Debug, Default, PartialEq)]
+pub struct QuadVertices {
+    pub positions: Vec<f32>,
+    pub tex_coords: Vec<f32>,
+    pub colors: Vec<f32>,
+}
+
+impl QuadVertices {
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len() / 2
+    }
+}
+
+fn lerp_color(a: [f32; 4], b: [f32; 4], t: f32) -> [f32; 4] {
+    let mut color = a;
+    for (channel, end) in color.iter_mut().zip(b.iter()) {
+        *channel += (end - *channel) * t;
+    }
+    color
+}
+
+fn intersect(a: &Rect, b: &Rect) -> Rect {
+    Rect::new(a.bottom.max(b.bottom), a.top.min(b.top), a.left.max(b.left), a.right.min(b.right))
+}
+
+/// Collects coloured and textured quads for one draw call. Quads are drawn
+/// in ascending `z`, in the order they were added within a layer, and are
+/// cut to the clip rectangle that was active when they were added.
+pub struct QuadBatch {
+    quads: Vec<Quad>,
+    clips: Vec<Rect>,
+    z: i32,
+}
+
+impl QuadBatch {
+    pub fn new() -> Self {
+        Self {
+            quads: Vec::new(),
+            clips: Vec::new(),
+            z: 0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.quads.clear();
+        self.clips.clear();
+        self.z = 0;
+    }
+
+    /// Sets the layer for the quads added from now on.
+    pub fn set_z(&mut self, z: i32) {
+        self.z = z;
+    }
+
+    /// Restricts the following quads to `rect`, within any clip already
+    /// active.
+    pub fn push_clip(&mut self, rect: Rect) {
+        let rect = match self.clips.last() {
+            Some(clip) => intersect(clip, &rect),
+            None => rect,
+        };
+        self.clips.push(rect);
+    }
+
+    pub fn pop_clip(&mut self) {
+        self.clips.pop();
+    }
+
+    fn push(&mut self, rect: Rect, fill: Fill, colors: [[f32; 4]; 4]) {
+        self.quads.push(Quad {
+            rect: rect,
+            fill: fill,
+            colors: colors,
+            z: self.z,
+            clip: self.clips.last().cloned(),
+        });
+    }
+
+    pub fn rect(&mut self, rect: Rect, color: [f32; 4]) {
+        self.push(rect, Fill::Solid, [color; 4]);
+    }
+
+    /// Corner colours in the order top left, bottom left, top right, bottom right.
+    pub fn gradient(&mut self, rect: Rect, colors: [[f32; 4]; 4]) {
+        self.push(rect, Fill::Solid, colors);
+    }
+
+    pub fn textured(&mut self, rect: Rect, fill: Fill, color: [f32; 4]) {
+        self.push(rect, fill, [color; 4]);
+    }
+
+    /// Lays out `text` downwards from `top`, wrapping lines wider than
+    /// `max_width` pixels.
+    pub fn text(
+        &mut self,
+        font: &Font,
+        text: &str,
+        left: f32,
+        top: f32,
+        scale: f32,
+        max_width: Option<f32>,
+        color: [f32; 4],
+    ) {
+        for glyph in font.glyph_quads(text, left, top, scale, max_width) {
+            let fill = match font.kind {
+                FontKind::Bitmap => Fill::Bitmap { uvs: glyph.uvs },
+                FontKind::Sdf { .. } => Fill::Sdf { uvs: glyph.uvs, smoothing: font.get_smoothing(scale) },
+            };
+            self.textured(Rect::new(glyph.bottom, glyph.top, glyph.left, glyph.right), fill, color);
+        }
+    }
+
+    pub fn build(&self) -> QuadVertices {
+        let mut order: Vec<&Quad> = self.quads.iter().collect();
+        order.sort_by_key(|quad| quad.z);
+
+        let mut vertices = QuadVertices::default();
+        for quad in order {
+            let rect = match quad.clip {
+                Some(clip) => intersect(&quad.rect, &clip),
+                None => quad.rect,
+            };
+            if rect.left >= rect.right || rect.bottom >= rect.top {
+                continue;
+            }
+
+            let width = quad.rect.right - quad.rect.left;
+            let height = quad.rect.top - quad.rect.bottom;
+            let (mode, smoothing, uvs) = match quad.fill {
+                Fill::Solid => (0.0, 0.0, (0.0, 0.0, 0.0, 0.0)),
+                Fill::Bitmap { uvs } => (1.0, 0.0, uvs),
+                Fill::Sdf { uvs, smoothing } => (2.0, smoothing, uvs),
+            };
+            let (u1, v1, u2, v2) = uvs;
+            let [top_left, bottom_left, top_right, bottom_right] = quad.colors;
+
+            // Top left, bottom left, top right, top right, bottom left, bottom right.
+            for &(x, y) in [
+                (rect.left, rect.top),
+                (rect.left, rect.bottom),
+                (rect.right, rect.top),
+                (rect.right, rect.top),
+                (rect.left, rect.bottom),
+                (rect.right, rect.bottom),
+            ].iter() {
+                let s = (x - quad.rect.left) / width;
+                let t = (quad.rect.top - y) / height;
+                let color = lerp_color(lerp_color(top_left, top_right, s), lerp_color(bottom_left, bottom_right, s), t);
+
+                vertices.positions.extend_from_slice(&[x, y]);
+                vertices.tex_coords.extend_from_slice(&[u1 + (u2 - u1) * s, v1 + (v2 - v1) * t, mode, smoothing]);
+                vertices.colors.extend_from_slice(&color);
+            }
+        }
+        vertices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+    const BLUE: [f32; 4] = [0.0, 0.0, 1.0, 1.0];
+
+    #[test]
+    fn draws_higher_layers_last_and_keeps_order_within_a_layer() {
+        let mut batch = QuadBatch::new();
+        batch.set_z(1);
+        batch.rect(Rect::new(0.0, 1.0, 0.0, 1.0), RED);
+        batch.set_z(0);
+        batch.rect(Rect::new(0.0, 1.0, 2.0, 3.0), BLUE);
+        batch.rect(Rect::new(0.0, 1.0, 4.0, 5.0), BLUE);
+
+        let vertices = batch.build();
+        assert_eq!(vertices.vertex_count(), 18);
+        assert_eq!(vertices.positions[0], 2.0);
+        assert_eq!(vertices.positions[12], 4.0);
+        assert_eq!(vertices.positions[24], 0.0);
+        assert_eq!(&vertices.colors[48..52], &RED);
+    }
+
+    #[test]
+    fn clips_positions_texture_coordinates_and_colours() {
+        let mut batch = QuadBatch::new();
+        batch.push_clip(Rect::new(0.0, 100.0, 50.0, 100.0));
+        batch.push_clip(Rect::new(0.0, 5.0, 0.0, 100.0));
+        batch.rect(Rect::new(10.0, 20.0, 0.0, 10.0), RED);
+        batch.pop_clip();
+        batch.textured(Rect::new(0.0, 10.0, 0.0, 100.0), Fill::Bitmap { uvs: (0.0, 0.0, 1.0, 1.0) }, RED);
+        batch.gradient(Rect::new(0.0, 10.0, 0.0, 100.0), [RED, RED, BLUE, BLUE]);
+        batch.pop_clip();
+
+        let vertices = batch.build();
+        assert_eq!(vertices.vertex_count(), 12);
+        assert_eq!(&vertices.positions[..4], &[50.0, 10.0, 50.0, 0.0]);
+        assert_eq!(&vertices.tex_coords[..4], &[0.5, 0.0, 1.0, 0.0]);
+        assert_eq!(&vertices.colors[24..28], &[0.5, 0.0, 0.5, 1.0]);
+        assert_eq!(&vertices.colors[32..36], &BLUE);
+    }
+
+    #[test]
+    fn text_becomes_one_bitmap_quad_per_visible_glyph() {
+        let mut batch = QuadBatch::new();
+        batch.text(&Font::builtin(), "a b", 0.0, 20.0, 1.0, None, RED);
+
+        let vertices = batch.build();
+        assert_eq!(vertices.vertex_count(), 12);
+        assert!(vertices.tex_coords.chunks(4).all(|tex_coord| tex_coord[2] == 1.0));
+    }
+}
@@ -0,0 +1,556 @@
+use super::app_state::{AppState, Event, Pointer, Scene};
+use super::camera::{CameraMode, OrbitCamera};
+
+const MAGIC: &[u8; 4] = b"TCRC";
+const VERSION: u8 = 3;
+
+struct ByteWriter {
+    bytes: Vec<u8>,
+}
+
+impl ByteWriter {
+    fn put_u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    fn put_i16(&mut self, value: i16) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn put_i32(&mut self, value: i32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn put_u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn put_f32(&mut self, value: f32) {
+        self.bytes.extend_from_slice(&value.to_bits().to_le_bytes());
+    }
+
+    fn put_f32s(&mut self, values: &[f32]) {
+        values.iter().for_each(|&value| self.put_f32(value));
+    }
+
+    fn put_str(&mut self, value: &str) {
+        self.put_u32(value.len() as u32);
+        self.bytes.extend_from_slice(value.as_bytes());
+    }
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], String> {
+        if self.bytes.len() - self.position < count {
+            return Err(format!("Recording truncated at byte {}", self.position));
+        }
+
+        let slice = &self.bytes[self.position..self.position + count];
+        self.position += count;
+        Ok(slice)
+    }
+
+    fn get_u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn get_bool(&mut self) -> Result<bool, String> {
+        Ok(self.get_u8()? != 0)
+    }
+
+    fn get_i16(&mut self) -> Result<i16, String> {
+        let bytes = self.take(2)?;
+        Ok(i16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn get_i32(&mut self) -> Result<i32, String> {
+        let bytes = self.take(4)?;
+        Ok(i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn get_u32(&mut self) -> Result<u32, String> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn get_f32(&mut self) -> Result<f32, String> {
+        Ok(f32::from_bits(self.get_u32()?))
+    }
+
+    fn get_f32_3(&mut self) -> Result<[f32; 3], String> {
+        Ok([self.get_f32()?, self.get_f32()?, self.get_f32()?])
+    }
+
+    fn get_str(&mut self) -> Result<String, String> {
+        let length = self.get_u32()? as usize;
+        String::from_utf8(self.take(length)?.to_vec()).map_err(|err| format!("Invalid key in recording: {}", err))
+    }
+}
+
+fn encode_camera_mode(camera_mode: CameraMode) -> u8 {
+    match camera_mode {
+        CameraMode::Turntable => 0,
+        CameraMode::Orbit => 1,
+    }
+}
+
+fn decode_camera_mode(value: u8) -> Result<CameraMode, String> {
+    match value {
+        0 => Ok(CameraMode::Turntable),
+        1 => Ok(CameraMode::Orbit),
+        _ => Err(format!("Unknown camera mode {} in recording", value)),
+    }
+}
+
+fn encode_scene(scene: Scene) -> u8 {
+    match scene {
+        Scene::Cube => 0,
+        Scene::Graph => 1,
+    }
+}
+
+fn decode_scene(value: u8) -> Result<Scene, String> {
+    match value {
+        0 => Ok(Scene::Cube),
+        1 => Ok(Scene::Graph),
+        _ => Err(format!("Unknown scene {} in recording", value)),
+    }
+}
+
+fn encode_state(writer: &mut ByteWriter, state: &AppState) {
+    let camera = &state.camera;
+    writer.put_f32s(&[camera.pitch, camera.yaw, camera.zoom]);
+    writer.put_f32s(&camera.target);
+    writer.put_f32(camera.damping);
+    writer.put_f32s(&camera.default_target);
+    writer.put_f32s(&[camera.pitch_delta, camera.yaw_delta]);
+
+    writer.put_u8(encode_camera_mode(state.camera_mode));
+    writer.put_f32s(&[state.canvas_height, state.canvas_width]);
+    writer.put_f32s(&[state.control_bottom, state.control_top, state.control_left, state.control_right]);
+    match state.cursor {
+        Some((x, y)) => {
+            writer.put_u8(1);
+            writer.put_f32s(&[x, y]);
+        },
+        None => writer.put_u8(0),
+    }
+    writer.put_u8(state.held_keys);
+
+    for pointer in state.pointers.iter() {
+        match pointer {
+            Some(pointer) => {
+                writer.put_u8(1);
+                writer.put_i32(pointer.id);
+                writer.put_i16(pointer.button);
+                writer.put_f32s(&[pointer.x, pointer.y]);
+            },
+            None => writer.put_u8(0),
+        }
+    }
+
+    writer.put_f32s(&[state.rotation_x_axis, state.rotation_y_axis]);
+    writer.put_u8(encode_scene(state.scene));
+    for &flag in [state.show_axes, state.show_contours, state.show_wireframe].iter() {
+        writer.put_u8(flag as u8);
+    }
+    writer.put_f32(state.time);
+}
+
+fn decode_state(reader: &mut ByteReader) -> Result<AppState, String> {
+    let camera = OrbitCamera {
+        pitch: reader.get_f32()?,
+        yaw: reader.get_f32()?,
+        zoom: reader.get_f32()?,
+        target: reader.get_f32_3()?,
+        damping: reader.get_f32()?,
+        default_target: reader.get_f32_3()?,
+        pitch_delta: reader.get_f32()?,
+        yaw_delta: reader.get_f32()?,
+    };
+
+    let mut state = AppState {
+        camera: camera,
+        camera_mode: decode_camera_mode(reader.get_u8()?)?,
+        canvas_height: reader.get_f32()?,
+        canvas_width: reader.get_f32()?,
+        control_bottom: reader.get_f32()?,
+        control_top: reader.get_f32()?,
+        control_left: reader.get_f32()?,
+        control_right: reader.get_f32()?,
+        cursor: if reader.get_bool()? { Some((reader.get_f32()?, reader.get_f32()?)) } else { None },
+        held_keys: reader.get_u8()?,
+        ..AppState::new()
+    };
+
+    for slot in state.pointers.iter_mut() {
+        *slot = if reader.get_bool()? {
+            Some(Pointer {
+                id: reader.get_i32()?,
+                button: reader.get_i16()?,
+                x: reader.get_f32()?,
+                y: reader.get_f32()?,
+            })
+        } else {
+            None
+        };
+    }
+
+    state.rotation_x_axis = reader.get_f32()?;
+    state.rotation_y_axis = reader.get_f32()?;
+    state.scene = decode_scene(reader.get_u8()?)?;
+    state.show_axes = reader.get_bool()?;
+    state.show_contours = reader.get_bool()?;
+    state.show_wireframe = reader.get_bool()?;
+    state.time = reader.get_f32()?;
+    Ok(state)
+}
+
+fn encode_event(writer: &mut ByteWriter, event: &Event) {
+    match *event {
+        Event::Resize { canvas_height, canvas_width } => {
+            writer.put_u8(0);
+            writer.put_f32s(&[canvas_height, canvas_width]);
+        },
+        Event::Tick { time } => {
+            writer.put_u8(1);
+            writer.put_f32(time);
+        },
+        Event::PointerDown { id, x, y, button } => {
+            writer.put_u8(2);
+            writer.put_i32(id);
+            writer.put_f32s(&[x, y]);
+            writer.put_i16(button);
+        },
+        Event::PointerMove { id, x, y } => {
+            writer.put_u8(3);
+            writer.put_i32(id);
+            writer.put_f32s(&[x, y]);
+        },
+        Event::PointerUp { id } => {
+            writer.put_u8(4);
+            writer.put_i32(id);
+        },
+        Event::Wheel { delta_y, is_pinch } => {
+            writer.put_u8(5);
+            writer.put_f32(delta_y);
+            writer.put_u8(is_pinch as u8);
+        },
+        Event::Key { ref key, is_down } => {
+            writer.put_u8(6);
+            writer.put_str(key);
+            writer.put_u8(is_down as u8);
+        },
+        Event::Look { rotation_x_delta, rotation_y_delta } => {
+            writer.put_u8(7);
+            writer.put_f32s(&[rotation_x_delta, rotation_y_delta]);
+        },
+        Event::Pan { dx, dy } => {
+            writer.put_u8(8);
+            writer.put_f32s(&[dx, dy]);
+        },
+        Event::Zoom { factor } => {
+            writer.put_u8(9);
+            writer.put_f32(factor);
+        },
+        Event::ResetCamera => writer.put_u8(10),
+        Event::SetCameraMode(camera_mode) => {
+            writer.put_u8(11);
+            writer.put_u8(encode_camera_mode(camera_mode));
+        },
+        Event::SetCameraTarget(target) => {
+            writer.put_u8(12);
+            writer.put_f32s(&target);
+        },
+        Event::SetCameraDamping(damping) => {
+            writer.put_u8(13);
+            writer.put_f32(damping);
+        },
+        Event::ReleaseKeys => writer.put_u8(14),
+        Event::SetScene(scene) => {
+            writer.put_u8(15);
+            writer.put_u8(encode_scene(scene));
+        },
+        Event::SetAxes(enabled) => {
+            writer.put_u8(16);
+            writer.put_u8(enabled as u8);
+        },
+        Event::SetContours(enabled) => {
+            writer.put_u8(17);
+            writer.put_u8(enabled as u8);
+        },
+        Event::SetWireframe(enabled) => {
+            writer.put_u8(18);
+            writer.put_u8(enabled as u8);
+        },
+    }
+}
+
+fn decode_event(reader: &mut ByteReader) -> Result<Event, String> {
+    let tag = reader.get_u8()?;
+    Ok(match tag {
+        0 => Event::Resize { canvas_height: reader.get_f32()?, canvas_width: reader.get_f32()? },
+        1 => Event::Tick { time: reader.get_f32()? },
+        2 => Event::PointerDown {
+            id: reader.get_i32()?,
+            x: reader.get_f32()?,
+            y: reader.get_f32()?,
+            button: reader.get_i16()?,
+        },
+        3 => Event::PointerMove { id: reader.get_i32()?, x: reader.get_f32()?, y: reader.get_f32()? },
+        4 => Event::PointerUp { id: reader.get_i32()? },
+        5 => Event::Wheel { delta_y: reader.get_f32()?, is_pinch: reader.get_bool()? },
+        6 => Event::Key { key: reader.get_str()?, is_down: reader.get_bool()? },
+        7 => Event::Look { rotation_x_delta: reader.get_f32()?, rotation_y_delta: reader.get_f32()? },
+        8 => Event::Pan { dx: reader.get_f32()?, dy: reader.get_f32()? },
+        9 => Event::Zoom { factor: reader.get_f32()? },
+        10 => Event::ResetCamera,
+        11 => Event::SetCameraMode(decode_camera_mode(reader.get_u8()?)?),
+        12 => Event::SetCameraTarget(reader.get_f32_3()?),
+        13 => Event::SetCameraDamping(reader.get_f32()?),
+        14 => Event::ReleaseKeys,
+        15 => Event::SetScene(decode_scene(reader.get_u8()?)?),
+        16 => Event::SetAxes(reader.get_bool()?),
+        17 => Event::SetContours(reader.get_bool()?),
+        18 => Event::SetWireframe(reader.get_bool()?),
+        _ => return Err(format!("Unknown event tag {} at byte {}", tag, reader.position - 1)),
+    })
+}
+
+/// The state when recording started and every event applied since, enough to
+/// rebuild the exact final state. Changes to the plotted data, such as a new
+/// function, heightmap or grid, live outside `AppState`; the first one made
+/// while recording is noted so the recording can be refused for replay.
+pub struct Recording {
+    pub initial_state: AppState,
+    pub events: Vec<Event>,
+    pub untracked_change: Option<String>,
+}
+
+impl Recording {
+    pub fn new(initial_state: AppState) -> Self {
+        Self {
+            initial_state: initial_state,
+            events: Vec::new(),
+            untracked_change: None,
+        }
+    }
+
+    pub fn record(&mut self, event: &Event) {
+        self.events.push(event.clone());
+    }
+
+    pub fn note_untracked_change(&mut self, change: &str) {
+        if self.untracked_change.is_none() {
+            self.untracked_change = Some(change.to_string());
+        }
+    }
+
+    /// Little-endian binary log with floats stored as raw bits so a decoded
+    /// recording replays bit-for-bit.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = ByteWriter { bytes: Vec::new() };
+        writer.bytes.extend_from_slice(MAGIC);
+        writer.put_u8(VERSION);
+        encode_state(&mut writer, &self.initial_state);
+
+        writer.put_u32(self.events.len() as u32);
+        for event in self.events.iter() {
+            encode_event(&mut writer, event);
+        }
+
+        match self.untracked_change {
+            Some(ref change) => {
+                writer.put_u8(1);
+                writer.put_str(change);
+            },
+            None => writer.put_u8(0),
+        }
+        writer.bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = ByteReader { bytes: bytes, position: 0 };
+        if reader.take(MAGIC.len()).ok() != Some(&MAGIC[..]) {
+            return Err(String::from("Not a recording"));
+        }
+
+        let version = reader.get_u8()?;
+        if version != VERSION {
+            return Err(format!("Unsupported recording version {}", version));
+        }
+
+        let initial_state = decode_state(&mut reader)?;
+        let event_count = reader.get_u32()? as usize;
+        let mut events = Vec::with_capacity(event_count.min(bytes.len()));
+        for _ in 0..event_count {
+            events.push(decode_event(&mut reader)?);
+        }
+        let untracked_change = if reader.get_bool()? { Some(reader.get_str()?) } else { None };
+
+        if reader.position != bytes.len() {
+            return Err(format!("Unexpected data after byte {}", reader.position));
+        }
+
+        Ok(Self {
+            initial_state: initial_state,
+            events: events,
+            untracked_change: untracked_change,
+        })
+    }
+}
+
+/// Steps through a recording one frame, i.e. up to and including the next
+/// `Tick`, per call.
+pub struct Replay {
+    recording: Recording,
+    position: usize,
+}
+
+impl Replay {
+    pub fn new(recording: Recording) -> Result<Self, String> {
+        if let Some(ref change) = recording.untracked_change {
+            return Err(format!("Recording can't be replayed because {} changed while it was made", change));
+        }
+
+        Ok(Self {
+            recording: recording,
+            position: 0,
+        })
+    }
+
+    pub fn initial_state(&self) -> AppState {
+        self.recording.initial_state
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.recording.events.len()
+    }
+
+    pub fn next_frame(&mut self) -> &[Event] {
+        let start = self.position;
+        let events = &self.recording.events[start..];
+        let length = events
+            .iter()
+            .position(|event| matches!(event, Event::Tick { .. }))
+            .map_or(events.len(), |tick| tick + 1);
+
+        self.position += length;
+        &self.recording.events[start..self.position]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_final_state(recording: &Recording) -> AppState {
+        recording.events.iter().fold(recording.initial_state, |state, event| state.apply(event))
+    }
+
+    fn get_session() -> Recording {
+        let mut state = AppState::new().apply(&Event::Resize { canvas_height: 720.0, canvas_width: 1280.0 });
+        state = state.apply(&Event::Tick { time: 16.7 });
+
+        let mut recording = Recording::new(state);
+        let events = vec![
+            Event::SetCameraMode(CameraMode::Orbit),
+            Event::PointerDown { id: 1, x: 640.0, y: 360.0, button: 0 },
+            Event::PointerMove { id: 1, x: 652.3, y: 347.9 },
+            Event::Tick { time: 33.4 },
+            Event::PointerDown { id: 2, x: 800.0, y: 400.0, button: 0 },
+            Event::PointerMove { id: 2, x: 845.1, y: 421.7 },
+            Event::PointerUp { id: 2 },
+            Event::PointerUp { id: 1 },
+            Event::Tick { time: 50.1 },
+            Event::Key { key: String::from("ArrowLeft"), is_down: true },
+            Event::Wheel { delta_y: -53.0, is_pinch: true },
+            Event::Tick { time: 66.8 },
+            Event::Key { key: String::from("ArrowLeft"), is_down: false },
+            Event::Look { rotation_x_delta: 0.013, rotation_y_delta: -0.021 },
+            Event::Pan { dx: 0.004, dy: -0.002 },
+            Event::Zoom { factor: 1.017 },
+            Event::SetCameraTarget([0.1, -0.2, 0.3]),
+            Event::SetCameraDamping(0.3),
+            Event::Resize { canvas_height: 600.0, canvas_width: 800.0 },
+            Event::Tick { time: 83.5 },
+            Event::ResetCamera,
+            Event::Key { key: String::from("w"), is_down: true },
+            Event::ReleaseKeys,
+            Event::SetScene(Scene::Graph),
+            Event::SetAxes(true),
+            Event::SetContours(true),
+            Event::SetWireframe(true),
+            Event::SetWireframe(false),
+            Event::PointerDown { id: 5, x: 10.0, y: 20.0, button: 2 },
+            Event::Tick { time: 100.2 },
+        ];
+
+        for event in events.iter() {
+            state = state.apply(event);
+            recording.record(event);
+        }
+
+        assert_eq!(format!("{:?}", get_final_state(&recording)), format!("{:?}", state));
+        recording
+    }
+
+    #[test]
+    fn replayed_state_matches_recorded_state_bit_for_bit() {
+        let recording = get_session();
+        let recorded_state = get_final_state(&recording);
+
+        let mut replay = Replay::new(Recording::decode(&recording.encode()).unwrap()).unwrap();
+        let mut state = replay.initial_state();
+        let mut frames = 0;
+        while !replay.is_finished() {
+            state = replay.next_frame().iter().fold(state, |state, event| state.apply(event));
+            frames += 1;
+        }
+
+        assert_eq!(frames, 5);
+        assert_eq!(format!("{:?}", state), format!("{:?}", recorded_state));
+        assert_eq!(state.time.to_bits(), recorded_state.time.to_bits());
+        assert_eq!(state.camera.yaw.to_bits(), recorded_state.camera.yaw.to_bits());
+    }
+
+    #[test]
+    fn encoding_round_trips() {
+        let recording = get_session();
+        let decoded = Recording::decode(&recording.encode()).unwrap();
+
+        assert_eq!(decoded.events, recording.events);
+        assert_eq!(decoded.encode(), recording.encode());
+    }
+
+    #[test]
+    fn refuses_to_replay_untracked_changes() {
+        let mut recording = get_session();
+        recording.note_untracked_change("the function");
+        recording.note_untracked_change("the grid");
+
+        let decoded = Recording::decode(&recording.encode()).unwrap();
+        assert_eq!(decoded.untracked_change.as_deref(), Some("the function"));
+        assert_eq!(
+            Replay::new(decoded).err().unwrap(),
+            "Recording can't be replayed because the function changed while it was made",
+        );
+    }
+
+    #[test]
+    fn rejects_corrupt_recordings() {
+        let bytes = get_session().encode();
+
+        assert!(Recording::decode(b"nope").is_err());
+        assert!(Recording::decode(&bytes[..bytes.len() - 1]).is_err());
+
+        let mut extra = bytes.clone();
+        extra.push(0);
+        assert!(Recording::decode(&extra).is_err());
+    }
+}
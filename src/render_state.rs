@@ -0,0 +1,168 @@
+use web_sys::WebGlRenderingContext as GL;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Cull {
+    None,
+    Back,
+    Front,
+}
+
+/// The fixed-function state a draw depends on. Blending is always
+/// `SRC_ALPHA, ONE_MINUS_SRC_ALPHA` and the depth offset always pushes
+/// polygons back by one unit when enabled.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RenderState {
+    pub depth_test: bool,
+    pub depth_write: bool,
+    pub depth_offset: bool,
+    pub cull: Cull,
+    pub blend: bool,
+}
+
+impl RenderState {
+    /// Depth tested and written; draw front to back so hidden fragments fail
+    /// the depth test early.
+    pub const OPAQUE: Self = Self {
+        depth_test: true,
+        depth_write: true,
+        depth_offset: false,
+        cull: Cull::Back,
+        blend: false,
+    };
+
+    /// Depth tested against the opaque pass but not written, so layers behind
+    /// still show; draw back to front after everything opaque.
+    pub const TRANSPARENT: Self = Self {
+        depth_test: true,
+        depth_write: false,
+        depth_offset: false,
+        cull: Cull::Back,
+        blend: true,
+    };
+
+    /// Screen-space UI, text and labels drawn over the scene in order.
+    pub const OVERLAY: Self = Self {
+        depth_test: false,
+        depth_write: false,
+        depth_offset: false,
+        cull: Cull::None,
+        blend: true,
+    };
+
+    pub const fn with_cull(self, cull: Cull) -> Self {
+        Self {
+            cull: cull,
+            ..self
+        }
+    }
+
+    /// Lets lines drawn on the surface afterwards win the depth test.
+    pub const fn with_depth_offset(self) -> Self {
+        Self {
+            depth_offset: true,
+            ..self
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum StateChange {
+    DepthTest(bool),
+    DepthWrite(bool),
+    DepthOffset(bool),
+    CullFace(bool),
+    CullMode(u32),
+    Blend(bool),
+}
+
+/// The GL calls needed to go from `current` to `next`, or every call when the
+/// current state is unknown.
+fn get_changes(current: Option<RenderState>, next: RenderState) -> Vec<StateChange> {
+    let mut changes = Vec::new();
+    let differs = |field: fn(&RenderState) -> bool| !matches!(current, Some(current) if field(&current) == field(&next));
+
+    if differs(|state| state.depth_test) {
+        changes.push(StateChange::DepthTest(next.depth_test));
+    }
+    if differs(|state| state.depth_write) {
+        changes.push(StateChange::DepthWrite(next.depth_write));
+    }
+    if differs(|state| state.depth_offset) {
+        changes.push(StateChange::DepthOffset(next.depth_offset));
+    }
+    if differs(|state| state.cull != Cull::None) {
+        changes.push(StateChange::CullFace(next.cull != Cull::None));
+    }
+    if next.cull != Cull::None && !matches!(current, Some(current) if current.cull == next.cull) {
+        changes.push(StateChange::CullMode(if next.cull == Cull::Front { GL::FRONT } else { GL::BACK }));
+    }
+    if differs(|state| state.blend) {
+        changes.push(StateChange::Blend(next.blend));
+    }
+    changes
+}
+
+fn set_capability(gl: &GL, capability: u32, enabled: bool) {
+    if enabled {
+        gl.enable(capability);
+    } else {
+        gl.disable(capability);
+    }
+}
+
+/// Remembers the last `RenderState` applied so switching passes only issues
+/// the GL calls for what actually changed.
+pub struct RenderStateTracker {
+    current: Option<RenderState>,
+}
+
+impl RenderStateTracker {
+    pub fn new() -> Self {
+        Self {
+            current: None,
+        }
+    }
+
+    pub fn apply(&mut self, gl: &GL, state: RenderState) {
+        for change in get_changes(self.current, state) {
+            match change {
+                StateChange::DepthTest(enabled) => set_capability(gl, GL::DEPTH_TEST, enabled),
+                StateChange::DepthWrite(enabled) => gl.depth_mask(enabled),
+                StateChange::DepthOffset(enabled) => set_capability(gl, GL::POLYGON_OFFSET_FILL, enabled),
+                StateChange::CullFace(enabled) => set_capability(gl, GL::CULL_FACE, enabled),
+                StateChange::CullMode(mode) => gl.cull_face(mode),
+                StateChange::Blend(enabled) => set_capability(gl, GL::BLEND, enabled),
+            }
+        }
+        self.current = Some(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sets_everything_when_the_state_is_unknown() {
+        assert_eq!(get_changes(None, RenderState::OVERLAY), vec![
+            StateChange::DepthTest(false),
+            StateChange::DepthWrite(false),
+            StateChange::DepthOffset(false),
+            StateChange::CullFace(false),
+            StateChange::Blend(true),
+        ]);
+    }
+
+    #[test]
+    fn only_changes_what_differs() {
+        assert!(get_changes(Some(RenderState::OPAQUE), RenderState::OPAQUE).is_empty());
+        assert_eq!(get_changes(Some(RenderState::OPAQUE), RenderState::TRANSPARENT), vec![
+            StateChange::DepthWrite(false),
+            StateChange::Blend(true),
+        ]);
+        assert_eq!(
+            get_changes(Some(RenderState::TRANSPARENT), RenderState::TRANSPARENT.with_cull(Cull::Front)),
+            vec![StateChange::CullMode(GL::FRONT)],
+        );
+    }
+}
@@ -0,0 +1,14 @@
+pub const SHADER: &str = r#"
+    precision mediump float;
+
+    uniform sampler2D uColorMap;
+    uniform float uOpacity;
+
+    varying mediump float vColorValue;
+    varying lowp vec3 vLighting;
+
+    void main() {
+        vec3 baseColor = texture2D(uColorMap, vec2(vColorValue, 0.5)).rgb;
+        gl_FragColor = vec4(baseColor * vLighting, uOpacity);
+    }
+"#;
@@ -1,2 +1,4 @@
-pub mod color_2d;
+pub mod color_map_lighting;
+pub mod quad_batch_2d;
+pub mod uniform_color;
 pub mod varying_color_from_vertex;
\ No newline at end of file
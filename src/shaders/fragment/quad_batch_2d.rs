@@ -0,0 +1,20 @@
+pub const SHADER: &str = r#"
+    precision mediump float;
+
+    uniform sampler2D uAtlas;
+    uniform float uOpacity;
+
+    varying mediump vec4 vTexCoord;
+    varying lowp vec4 vColor;
+
+    void main() {
+        float coverage = 1.0;
+        if (vTexCoord.z > 1.5) {
+            float distance = texture2D(uAtlas, vTexCoord.xy).a;
+            coverage = smoothstep(0.5 - vTexCoord.w, 0.5 + vTexCoord.w, distance);
+        } else if (vTexCoord.z > 0.5) {
+            coverage = texture2D(uAtlas, vTexCoord.xy).a;
+        }
+        gl_FragColor = vec4(vColor.rgb, vColor.a * coverage * uOpacity);
+    }
+"#;
@@ -3,9 +3,11 @@ pub const SHADER: &str = r#"
     attribute float aY;
     attribute vec3 aVertexNormal;
 
+    uniform vec2 uColorRange;
     uniform mat4 uNormalsRotation;
     uniform mat4 uProjection;
-    varying lowp vec4 vColor;
+    varying mediump float vColorValue;
+    varying lowp vec3 vLighting;
 
     void main() {
         gl_Position = uProjection * vec4(aPosition.x, aY, aPosition.z, 1.0);
@@ -16,9 +18,9 @@ pub const SHADER: &str = r#"
 
         vec4 transformedNormal = uNormalsRotation * vec4(aVertexNormal, 1.0);
         float directional = max(dot(transformedNormal.xyz, directionalVector), 0.0);
-        vec3 vLighting = ambientLight + (directionalLightColor * directional);
-        vec3 baseColor = vec3(0.5, 0.5, 0.8);
+        vLighting = ambientLight + (directionalLightColor * directional);
 
-        vColor = vec4(baseColor * vLighting, 1.0);
+        float colorSpan = max(uColorRange.y - uColorRange.x, 0.000001);
+        vColorValue = clamp((aY - uColorRange.x) / colorSpan, 0.0, 1.0);
     }
 "#;
\ No newline at end of file
@@ -0,0 +1,11 @@
+pub const SHADER: &str = r#"
+    attribute vec4 aPosition;
+    attribute float aY;
+
+    uniform mat4 uProjection;
+    uniform float uYOffset;
+
+    void main() {
+        gl_Position = uProjection * vec4(aPosition.x, aPosition.y + aY + uYOffset, aPosition.z, 1.0);
+    }
+"#;
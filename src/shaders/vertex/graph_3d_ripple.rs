@@ -0,0 +1,37 @@
+pub const SHADER: &str = r#"
+    attribute vec4 aPosition;
+
+    uniform float uAmplitude;
+    uniform float uFrequency;
+    uniform vec2 uColorRange;
+    uniform mat4 uNormalsRotation;
+    uniform mat4 uProjection;
+    uniform float uTime;
+    varying mediump float vColorValue;
+    varying lowp vec3 vLighting;
+
+    void main() {
+        float distance = length(aPosition.xz);
+        float phase = uFrequency * distance + uTime;
+        float y = uAmplitude * sin(phase);
+
+        vec2 slope = vec2(0.0, 0.0);
+        if (distance > 0.0) {
+            slope = uAmplitude * cos(phase) * uFrequency * aPosition.xz / distance;
+        }
+        vec3 vertexNormal = normalize(vec3(-slope.x, 1.0, -slope.y));
+
+        gl_Position = uProjection * vec4(aPosition.x, y, aPosition.z, 1.0);
+
+        vec3 ambientLight = vec3(0.2, 0.2, 0.2);
+        vec3 directionalLightColor = vec3(1, 1, 1);
+        vec3 directionalVector = normalize(vec3(-0.85, 0.8, 0.75));
+
+        vec4 transformedNormal = uNormalsRotation * vec4(vertexNormal, 1.0);
+        float directional = max(dot(transformedNormal.xyz, directionalVector), 0.0);
+        vLighting = ambientLight + (directionalLightColor * directional);
+
+        float colorSpan = max(uColorRange.y - uColorRange.x, 0.000001);
+        vColorValue = clamp((y - uColorRange.x) / colorSpan, 0.0, 1.0);
+    }
+"#;
@@ -1,4 +1,5 @@
-pub mod color_2d;
-pub mod color_2d_gradient;
 pub mod graph_3d;
-pub mod cube_3d;
\ No newline at end of file
+pub mod graph_3d_lines;
+pub mod graph_3d_ripple;
+pub mod cube_3d;
+pub mod quad_batch_2d;
\ No newline at end of file
@@ -0,0 +1,15 @@
+pub const SHADER: &str = r#"
+    attribute vec2 aPosition;
+    attribute vec4 aTexCoord;
+    attribute vec4 aColor;
+
+    uniform vec2 uCanvasSize;
+    varying mediump vec4 vTexCoord;
+    varying lowp vec4 vColor;
+
+    void main() {
+        vTexCoord = aTexCoord;
+        vColor = aColor;
+        gl_Position = vec4(2.0 * aPosition / uCanvasSize - 1.0, 0.0, 1.0);
+    }
+"#;
@@ -0,0 +1,347 @@
+use std::rc::Rc;
+use super::font::Font;
+
+const BUTTON_COLOR: [f32; 4] = [0.2, 0.2, 0.25, 0.85];
+const BUTTON_HOVER_COLOR: [f32; 4] = [0.3, 0.3, 0.4, 0.9];
+const BUTTON_PRESSED_COLOR: [f32; 4] = [0.15, 0.35, 0.6, 0.95];
+const SLOT_COLOR: [f32; 4] = [0.1, 0.1, 0.1, 0.6];
+const SLOT_SELECTED_COLOR: [f32; 4] = [0.95, 0.95, 0.95, 0.9];
+const HEALTH_EMPTY_COLOR: [f32; 4] = [0.8, 0.1, 0.1, 1.0];
+const HEALTH_FULL_COLOR: [f32; 4] = [0.2, 0.85, 0.3, 1.0];
+const TEXT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const LABEL_SCALE: f32 = 2.0;
+const BORDER: f32 = 2.0;
+
+/// A screen-space rectangle in pixels, with y measured up from the bottom of
+/// the canvas.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rect {
+    pub bottom: f32,
+    pub top: f32,
+    pub left: f32,
+    pub right: f32,
+}
+
+impl Rect {
+    pub fn new(bottom: f32, top: f32, left: f32, right: f32) -> Self {
+        Self {
+            bottom: bottom,
+            top: top,
+            left: left,
+            right: right,
+        }
+    }
+
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.left && x < self.right && y >= self.bottom && y < self.top
+    }
+
+    pub fn inset(&self, by: f32) -> Self {
+        Self::new(self.bottom + by, self.top - by, self.left + by, self.right - by)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DrawCommand {
+    Rect { rect: Rect, color: [f32; 4] },
+    /// Corner colours in the order top left, bottom left, top right, bottom right.
+    Gradient { rect: Rect, colors: [[f32; 4]; 4] },
+    Text { text: String, left: f32, top: f32, scale: f32, max_width: Option<f32>, color: [f32; 4] },
+    /// Commands up to the matching `PopClip` are cut to `rect`.
+    PushClip { rect: Rect },
+    PopClip,
+    /// Later commands draw over lower layers regardless of order.
+    Layer { z: i32 },
+}
+
+/// Pointer state for one frame. Presses and releases are the edges seen
+/// since the last frame, so a tap shorter than a frame still clicks.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct UiInput {
+    pub cursor: Option<(f32, f32)>,
+    pub is_down: bool,
+    pub pressed_at: Option<(f32, f32)>,
+    pub released_at: Option<(f32, f32)>,
+}
+
+/// Immediate-mode widgets: each frame the caller declares the UI again and
+/// gets back the interactions, while the widgets queue what to draw.
+pub struct Ui {
+    font: Rc<Font>,
+    input: UiInput,
+    active: Option<u32>,
+    commands: Vec<DrawCommand>,
+    hit_rects: Vec<Rect>,
+}
+
+impl Ui {
+    pub fn new(font: Rc<Font>) -> Self {
+        Self {
+            font: font,
+            input: UiInput::default(),
+            active: None,
+            commands: Vec::new(),
+            hit_rects: Vec::new(),
+        }
+    }
+
+    /// Sets the font used to measure labels, which should be the one they are
+    /// drawn with.
+    pub fn set_font(&mut self, font: Rc<Font>) {
+        self.font = font;
+    }
+
+    pub fn begin_frame(&mut self, input: UiInput) {
+        if !self.input.is_down && self.input.released_at.is_none() {
+            self.active = None;
+        }
+
+        self.input = input;
+        self.commands.clear();
+        self.hit_rects.clear();
+    }
+
+    /// Whether a press at `(x, y)` would land on a widget declared last
+    /// frame rather than on the scene behind the UI.
+    pub fn is_over(&self, x: f32, y: f32) -> bool {
+        self.hit_rects.iter().any(|rect| rect.contains(x, y))
+    }
+
+    pub fn commands(&self) -> &[DrawCommand] {
+        &self.commands
+    }
+
+    pub fn is_hovered(&self, rect: &Rect) -> bool {
+        matches!(self.input.cursor, Some((x, y)) if rect.contains(x, y))
+    }
+
+    /// Tracks a press that starts on `rect` and reports a click when it is
+    /// released still over it.
+    fn interact(&mut self, id: u32, rect: &Rect) -> bool {
+        if matches!(self.input.pressed_at, Some((x, y)) if rect.contains(x, y)) {
+            self.active = Some(id);
+        }
+
+        match self.input.released_at {
+            Some((x, y)) if self.active == Some(id) => {
+                self.active = None;
+                rect.contains(x, y)
+            },
+            _ => false,
+        }
+    }
+
+    fn fill(&mut self, rect: Rect, color: [f32; 4]) {
+        self.commands.push(DrawCommand::Rect { rect: rect, color: color });
+    }
+
+    /// A filled rectangle that keeps presses on it away from the scene.
+    pub fn panel(&mut self, rect: Rect, color: [f32; 4]) {
+        self.hit_rects.push(rect);
+        self.fill(rect, color);
+    }
+
+    pub fn set_layer(&mut self, z: i32) {
+        self.commands.push(DrawCommand::Layer { z: z });
+    }
+
+    pub fn push_clip(&mut self, rect: Rect) {
+        self.commands.push(DrawCommand::PushClip { rect: rect });
+    }
+
+    pub fn pop_clip(&mut self) {
+        self.commands.push(DrawCommand::PopClip);
+    }
+
+    pub fn label(&mut self, text: &str, left: f32, bottom: f32) {
+        self.commands.push(DrawCommand::Text {
+            text: text.to_string(),
+            left: left,
+            top: bottom + self.font.line_height * LABEL_SCALE,
+            scale: LABEL_SCALE,
+            max_width: None,
+            color: TEXT_COLOR,
+        });
+    }
+
+    /// Height of `text` wrapped to `width` pixels.
+    pub fn measure_text_block(&self, text: &str, width: f32, scale: f32) -> f32 {
+        self.font.wrap(text, Some(width / scale)).len() as f32 * self.font.line_height * scale
+    }
+
+    /// Wraps `text` to `width` pixels below `top` and returns the height it
+    /// takes up.
+    pub fn text_block(&mut self, text: &str, left: f32, top: f32, width: f32, scale: f32) -> f32 {
+        self.commands.push(DrawCommand::Text {
+            text: text.to_string(),
+            left: left,
+            top: top,
+            scale: scale,
+            max_width: Some(width),
+            color: TEXT_COLOR,
+        });
+        self.measure_text_block(text, width, scale)
+    }
+
+    pub fn button(&mut self, id: u32, rect: Rect, label: &str) -> bool {
+        let clicked = self.interact(id, &rect);
+        let color = if self.active == Some(id) && self.is_hovered(&rect) {
+            BUTTON_PRESSED_COLOR
+        } else if self.is_hovered(&rect) {
+            BUTTON_HOVER_COLOR
+        } else {
+            BUTTON_COLOR
+        };
+
+        self.panel(rect, color);
+        let text_height = self.font.line_height * LABEL_SCALE;
+        self.label(
+            label,
+            (rect.left + rect.right - self.font.measure(label, LABEL_SCALE)) / 2.0,
+            (rect.bottom + rect.top - text_height) / 2.0,
+        );
+        clicked
+    }
+
+    pub fn crosshair(&mut self, x: f32, y: f32, size: f32, thickness: f32, color: [f32; 4]) {
+        let half = thickness / 2.0;
+        self.fill(Rect::new(y - half, y + half, x - size, x + size), color);
+        self.fill(Rect::new(y - size, y - half, x - half, x + half), color);
+        self.fill(Rect::new(y + half, y + size, x - half, x + half), color);
+    }
+
+    /// A row of `slots` square slots centred on `center_x`, with the selected
+    /// slot outlined. Returns the slot clicked this frame, if any.
+    pub fn hotbar(
+        &mut self,
+        id: u32,
+        center_x: f32,
+        bottom: f32,
+        slot_size: f32,
+        slots: usize,
+        selected: usize,
+    ) -> Option<usize> {
+        let left = center_x - slot_size * slots as f32 / 2.0;
+        let mut clicked = None;
+
+        for slot in 0..slots {
+            let slot_left = left + slot_size * slot as f32;
+            let rect = Rect::new(bottom, bottom + slot_size, slot_left, slot_left + slot_size);
+            if self.interact(id + slot as u32, &rect) {
+                clicked = Some(slot);
+            }
+
+            if slot == selected {
+                self.panel(rect, SLOT_SELECTED_COLOR);
+            }
+            self.panel(rect.inset(BORDER), SLOT_COLOR);
+            self.label(&(slot + 1).to_string(), rect.left + 2.0 * BORDER, rect.bottom + 2.0 * BORDER);
+        }
+        clicked
+    }
+
+    /// Fills `fraction` of `rect`, shading from red when empty to green when
+    /// full.
+    pub fn health_bar(&mut self, rect: Rect, fraction: f32) {
+        let fraction = fraction.clamp(0.0, 1.0);
+        self.panel(rect, SLOT_COLOR);
+        if fraction <= 0.0 {
+            return;
+        }
+
+        let inner = rect.inset(BORDER);
+        let filled = Rect {
+            right: inner.left + (inner.right - inner.left) * fraction,
+            ..inner
+        };
+        let mut end_color = HEALTH_EMPTY_COLOR;
+        for (channel, full) in end_color.iter_mut().zip(HEALTH_FULL_COLOR.iter()) {
+            *channel += (full - *channel) * fraction;
+        }
+
+        self.commands.push(DrawCommand::Gradient {
+            rect: filled,
+            colors: [HEALTH_EMPTY_COLOR, HEALTH_EMPTY_COLOR, end_color, end_color],
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUTTON: Rect = Rect { bottom: 0.0, top: 20.0, left: 0.0, right: 100.0 };
+
+    fn input(x: f32, y: f32, is_down: bool) -> UiInput {
+        UiInput { cursor: Some((x, y)), is_down: is_down, ..UiInput::default() }
+    }
+
+    fn press(x: f32, y: f32) -> UiInput {
+        UiInput { pressed_at: Some((x, y)), ..input(x, y, true) }
+    }
+
+    fn release(x: f32, y: f32) -> UiInput {
+        UiInput { released_at: Some((x, y)), ..input(x, y, false) }
+    }
+
+    fn frame(ui: &mut Ui, input: UiInput) -> bool {
+        ui.begin_frame(input);
+        ui.button(1, BUTTON, "OK")
+    }
+
+    #[test]
+    fn button_clicks_on_release_over_it() {
+        let mut ui = Ui::new(Rc::new(Font::builtin()));
+
+        assert!(!frame(&mut ui, input(50.0, 10.0, false)));
+        assert!(!frame(&mut ui, press(50.0, 10.0)));
+        assert!(!frame(&mut ui, input(52.0, 11.0, true)));
+        assert!(frame(&mut ui, release(55.0, 12.0)));
+        assert!(!frame(&mut ui, input(55.0, 12.0, false)));
+    }
+
+    #[test]
+    fn button_clicks_on_a_press_and_release_within_one_frame() {
+        let mut ui = Ui::new(Rc::new(Font::builtin()));
+        let tap = UiInput { pressed_at: Some((50.0, 10.0)), ..release(50.0, 10.0) };
+
+        assert!(frame(&mut ui, tap));
+        assert!(!frame(&mut ui, input(50.0, 10.0, false)));
+    }
+
+    #[test]
+    fn button_ignores_drags_off_and_presses_started_elsewhere() {
+        let mut ui = Ui::new(Rc::new(Font::builtin()));
+
+        frame(&mut ui, press(50.0, 10.0));
+        assert!(!frame(&mut ui, release(150.0, 10.0)));
+
+        frame(&mut ui, press(150.0, 10.0));
+        frame(&mut ui, input(50.0, 10.0, true));
+        assert!(!frame(&mut ui, release(50.0, 10.0)));
+    }
+
+    #[test]
+    fn hotbar_reports_the_clicked_slot() {
+        let mut ui = Ui::new(Rc::new(Font::builtin()));
+        let mut hotbar = |input: UiInput| {
+            ui.begin_frame(input);
+            ui.hotbar(10, 100.0, 0.0, 50.0, 4, 0)
+        };
+
+        assert_eq!(hotbar(press(125.0, 20.0)), None);
+        assert_eq!(hotbar(release(125.0, 20.0)), Some(2));
+    }
+
+    #[test]
+    fn widgets_but_not_the_crosshair_take_presses() {
+        let mut ui = Ui::new(Rc::new(Font::builtin()));
+        ui.begin_frame(UiInput::default());
+        ui.button(1, BUTTON, "OK");
+        ui.crosshair(500.0, 500.0, 10.0, 2.0, [1.0; 4]);
+
+        assert!(ui.is_over(50.0, 10.0));
+        assert!(!ui.is_over(500.0, 500.0));
+        assert!(!ui.is_over(150.0, 10.0));
+    }
+}